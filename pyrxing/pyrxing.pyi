@@ -1,3 +1,8 @@
+import asyncio
+import builtins
+import os
+from collections.abc import Iterable
+from enum import IntFlag
 from typing import Any, Literal, Protocol
 
 
@@ -39,8 +44,71 @@ BarcodeFormat = Literal[
     "UPCE",
 ]
 
+Binarizer = Literal["LocalAverage", "GlobalHistogram", "FixedThreshold", "BoolCast"]
+
+TextMode = Literal["Plain", "ECI", "HRI", "Hex", "Escaped"]
+
+EanAddOnSymbol = Literal["Ignore", "Read", "Require"]
+
+# luma with Rec. 601 or Rec. 709 weights, a single channel, or the brightest or darkest one
+Channel = Literal["Rec601", "Rec709", "Red", "Green", "Blue", "Max", "Min"]
+
+# channel order of color arrays; OpenCV frames are BGR
+ColorOrder = Literal["RGB", "BGR"]
+
+ErrorKind = Literal["Format", "Checksum", "Unsupported"]
+
+ContentType = Literal["Text", "Binary", "Mixed", "GS1", "ISO15434", "UnknownECI"]
+
+
+class BarcodeFormats(IntFlag):
+    """set of formats to read, e.g. `BarcodeFormats.Any & ~BarcodeFormats.DataBar`;
+    a symbology such as DataBar or EANUPC includes all of its variants; an empty set reads nothing, while
+    `formats=None` or `[]` reads every format"""
+
+    Aztec = ...
+    AztecCode = ...
+    AztecRune = ...
+    Codabar = ...
+    Code39 = ...
+    Code93 = ...
+    Code128 = ...
+    CompactPDF417 = ...
+    DataBar = ...
+    DataBarExpanded = ...
+    DataBarExpandedStacked = ...
+    DataBarLimited = ...
+    DataBarOmni = ...
+    DataBarStacked = ...
+    DataBarStackedOmni = ...
+    DataMatrix = ...
+    DXFilmEdge = ...
+    EAN2 = ...
+    EAN5 = ...
+    EAN8 = ...
+    EAN13 = ...
+    EANUPC = ...
+    ISBN = ...
+    ITF = ...
+    MaxiCode = ...
+    MicroPDF417 = ...
+    MicroQRCode = ...
+    PDF417 = ...
+    PZN = ...
+    QRCode = ...
+    QRCodeModel1 = ...
+    QRCodeModel2 = ...
+    RMQRCode = ...
+    UPCA = ...
+    UPCE = ...
+    LinearCodes = ...
+    MatrixCodes = ...
+    RetailCodes = ...
+    Any = ...
+
 
 class ImageProtocol(Protocol):
+    """PIL image in mode 1, L, LA, La, P, PA, RGB, RGBA, RGBa, RGBX, CMYK, YCbCr, LAB, HSV, I, I;16 or F"""
     @property
     def width(self) -> int: ...
 
@@ -55,6 +123,33 @@ class ImageProtocol(Protocol):
     def load(self): ...
 
 
+class ArrayInterfaceProtocol(Protocol):
+    """uint8 array such as numpy.ndarray: 2D grayscale or HxWx3/HxWx4 RGB(A), non-negative strides allowed;
+    `data` must be a buffer, not a bare pointer"""
+
+    @property
+    def __array_interface__(self) -> dict[str, Any]: ...
+
+
+class BinaryFileProtocol(Protocol):
+    def read(self) -> bytes:
+        """return the encoded image file, e.g. PNG or JPEG"""
+
+
+# paths and binary files are decoded as image files, as are bytes, bytearray and 1-D buffers;
+# 2-D and 3-D buffers, e.g. memoryview, hold pixels laid out like ArrayInterfaceProtocol
+ImageInput = (
+    str
+    | os.PathLike[str]
+    | bytes
+    | bytearray
+    | memoryview
+    | BinaryFileProtocol
+    | ImageProtocol
+    | ArrayInterfaceProtocol
+)
+
+
 class BarcodeDecodeError(Exception): ...
 
 class ImageError(Exception): ...
@@ -66,16 +161,269 @@ class Point:
     @property
     def y(self) -> int: ...
 
+class BitMatrix:
+    @property
+    def width(self) -> int: ...
+
+    @property
+    def height(self) -> int: ...
+
+    @property
+    def row_stride(self) -> int:
+        """number of bytes per row in `bytes`"""
+
+    @property
+    def bytes(self) -> builtins.bytes:
+        """modules packed eight per byte, most significant bit first, set bits are dark"""
+
+    def to_list(self) -> list[list[bool]]: ...
+
 class DecodeResult:
     @property
     def text(self) -> str: ...
 
     @property
-    def points(self) -> list[Point]: ...
+    def bytes(self) -> builtins.bytes:
+        """raw decoded payload"""
+
+    @property
+    def bytes_eci(self) -> builtins.bytes:
+        """raw decoded payload including the symbology identifier and ECI designators"""
+
+    @property
+    def main_text(self) -> str:
+        """text without the EAN-2/EAN-5 add-on"""
+
+    @property
+    def add_on(self) -> str | None:
+        """EAN-2/EAN-5 add-on content, if one was read"""
+
+    @property
+    def points(self) -> list[Point]:
+        """corners of the symbol: top-left, top-right, bottom-right, bottom-left"""
 
     @property
     def format(self) -> str: ...
 
+    @property
+    def symbology_identifier(self) -> str:
+        """AIM symbology identifier such as ]Q1 or ]C0"""
+
+    @property
+    def content_type(self) -> ContentType: ...
+
+    @property
+    def ec_level(self) -> str | None:
+        """error correction level, e.g. H for QR Code or 70% for Aztec"""
+
+    @property
+    def version(self) -> str | None:
+        """symbol version or size, e.g. 1 for QR Code or 12x12 for Data Matrix"""
+
+    @property
+    def reader_init(self) -> bool: ...
+
+    @property
+    def orientation(self) -> int:
+        """rotation of the symbol in degrees"""
+
+    @property
+    def is_mirrored(self) -> bool: ...
+
+    @property
+    def is_inverted(self) -> bool: ...
+
+    @property
+    def is_valid(self) -> bool: ...
+
+    @property
+    def error_kind(self) -> ErrorKind | None: ...
+
+    @property
+    def error_message(self) -> str | None: ...
+
+    @property
+    def sequence_size(self) -> int | None:
+        """number of symbols in the Structured Append / Macro PDF417 sequence, if known"""
+
+    @property
+    def sequence_index(self) -> int | None:
+        """zero-based position of the symbol in its sequence"""
+
+    @property
+    def sequence_id(self) -> str | None: ...
+
+    @property
+    def symbol(self) -> BitMatrix | None:
+        """module matrix of 2D symbols as sampled from the image, without quiet zone"""
+
+class StructuredMessage:
+    @property
+    def format(self) -> BarcodeFormat: ...
+
+    @property
+    def id(self) -> str: ...
+
+    @property
+    def size(self) -> int | None:
+        """total number of segments, if known"""
+
+    @property
+    def text(self) -> str:
+        """text of the segments read so far, in sequence order"""
+
+    @property
+    def bytes(self) -> builtins.bytes: ...
+
+    @property
+    def missing(self) -> list[int]:
+        """indices of the segments not read yet"""
+
+    @property
+    def is_complete(self) -> bool: ...
+
+# `background` is the gray level that transparent pixels are composited over;
+# `window` is the (black, white) range of 16-bit, 32-bit and float images, by default
+# their darkest and brightest value, and ignored for 8-bit images and arrays
+# `channel` turns colors into gray levels, also as (r, g, b) weights; by default they
+# are read as Rec. 601 luma, for every kind of image
+# `color_order` is the channel order of HxWx3/HxWx4 arrays, e.g. "BGR" for OpenCV frames
+def read_barcode(
+    image: ImageInput,
+    *,
+    formats: list[BarcodeFormat] | BarcodeFormats | None = None,
+    binarizer: Binarizer = "LocalAverage",
+    text_mode: TextMode = "HRI",
+    ean_add_on: EanAddOnSymbol = "Ignore",
+    pure: bool = False,
+    return_errors: bool = False,
+    try_harder: bool = True,
+    try_rotate: bool = True,
+    try_invert: bool = True,
+    try_downscale: bool = True,
+    background: int = 255,
+    window: tuple[float, float] | None = None,
+    channel: Channel | tuple[float, float, float] | None = None,
+    color_order: ColorOrder = "RGB",
+) -> DecodeResult | None: ...
+def read_barcodes(
+    image: ImageInput,
+    *,
+    formats: list[BarcodeFormat] | BarcodeFormats | None = None,
+    binarizer: Binarizer = "LocalAverage",
+    text_mode: TextMode = "HRI",
+    ean_add_on: EanAddOnSymbol = "Ignore",
+    pure: bool = False,
+    return_errors: bool = False,
+    try_harder: bool = True,
+    try_rotate: bool = True,
+    try_invert: bool = True,
+    try_downscale: bool = True,
+    background: int = 255,
+    window: tuple[float, float] | None = None,
+    channel: Channel | tuple[float, float, float] | None = None,
+    color_order: ColorOrder = "RGB",
+) -> list[DecodeResult]: ...
+def read_barcode_async(
+    image: ImageInput,
+    *,
+    formats: list[BarcodeFormat] | BarcodeFormats | None = None,
+    binarizer: Binarizer = "LocalAverage",
+    text_mode: TextMode = "HRI",
+    ean_add_on: EanAddOnSymbol = "Ignore",
+    pure: bool = False,
+    return_errors: bool = False,
+    try_harder: bool = True,
+    try_rotate: bool = True,
+    try_invert: bool = True,
+    try_downscale: bool = True,
+    background: int = 255,
+    window: tuple[float, float] | None = None,
+    channel: Channel | tuple[float, float, float] | None = None,
+    color_order: ColorOrder = "RGB",
+) -> asyncio.Future[DecodeResult | None]:
+    """like `read_barcode`, decoding on a worker thread; must be called with an event loop running
+
+    file-like objects are read and PIL images copied before this returns, so they may be closed right away;
+    buffers and arrays are decoded in place and must not change until the future is done; paths are opened on
+    the worker
+    """
+    ...
+
+def read_barcodes_async(
+    image: ImageInput,
+    *,
+    formats: list[BarcodeFormat] | BarcodeFormats | None = None,
+    binarizer: Binarizer = "LocalAverage",
+    text_mode: TextMode = "HRI",
+    ean_add_on: EanAddOnSymbol = "Ignore",
+    pure: bool = False,
+    return_errors: bool = False,
+    try_harder: bool = True,
+    try_rotate: bool = True,
+    try_invert: bool = True,
+    try_downscale: bool = True,
+    background: int = 255,
+    window: tuple[float, float] | None = None,
+    channel: Channel | tuple[float, float, float] | None = None,
+    color_order: ColorOrder = "RGB",
+) -> asyncio.Future[list[DecodeResult]]:
+    """like `read_barcodes`, decoding on a worker thread; must be called with an event loop running
+
+    file-like objects are read and PIL images copied before this returns, so they may be closed right away;
+    buffers and arrays are decoded in place and must not change until the future is done; paths are opened on
+    the worker
+    """
+    ...
+
+def read_barcodes_batch(
+    images: Iterable[ImageInput],
+    *,
+    formats: list[BarcodeFormat] | BarcodeFormats | None = None,
+    workers: int = 0,
+    binarizer: Binarizer = "LocalAverage",
+    text_mode: TextMode = "HRI",
+    ean_add_on: EanAddOnSymbol = "Ignore",
+    pure: bool = False,
+    return_errors: bool = False,
+    try_harder: bool = True,
+    try_rotate: bool = True,
+    try_invert: bool = True,
+    try_downscale: bool = True,
+    background: int = 255,
+    window: tuple[float, float] | None = None,
+    channel: Channel | tuple[float, float, float] | None = None,
+    color_order: ColorOrder = "RGB",
+) -> list[list[DecodeResult] | Exception]:
+    """reads every image on `workers` threads (0 = one per cpu; more, e.g. for slow storage, start threads that are
+    kept for later batches), taking them from `images` as threads free up; results are in input order"""
+    ...
+
+def reassemble(results: list[DecodeResult]) -> list[StructuredMessage]: ...
+
+class BarcodeReader:
+    """reader configured once with formats and options, for decoding many images"""
 
-def read_barcode(image: str | ImageProtocol, *, formats: list[BarcodeFormat] | None = None) -> DecodeResult | None: ...
-def read_barcodes(image: str | ImageProtocol, *, formats: list[BarcodeFormat] | None = None) -> list[DecodeResult]: ...
+    def __init__(
+        self,
+        formats: list[BarcodeFormat] | BarcodeFormats | None = None,
+        *,
+        binarizer: Binarizer = "LocalAverage",
+        text_mode: TextMode = "HRI",
+        ean_add_on: EanAddOnSymbol = "Ignore",
+        pure: bool = False,
+        return_errors: bool = False,
+        try_harder: bool = True,
+        try_rotate: bool = True,
+        try_invert: bool = True,
+        try_downscale: bool = True,
+        background: int = 255,
+        window: tuple[float, float] | None = None,
+        channel: Channel | tuple[float, float, float] | None = None,
+        color_order: ColorOrder = "RGB",
+    ) -> None: ...
+    def read(self, image: ImageInput) -> DecodeResult | None: ...
+    def read_all(self, image: ImageInput) -> list[DecodeResult]: ...
+    def read_batch(
+        self, images: Iterable[ImageInput], *, workers: int = 0
+    ) -> list[list[DecodeResult] | Exception]: ...
@@ -0,0 +1,367 @@
+//! Conversion of the pixel data of PIL images, as returned by `tobytes()`,
+//! into an image zxing can read.
+//!
+//! See <https://pillow.readthedocs.io/en/stable/handbook/concepts.html#modes>
+//! for the modes and their layout.
+
+use reader_core::{Channel, Image, ImageFormat};
+
+/// How pixels that have no direct 8-bit luminance are mapped to one.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ImageOptions {
+    /// Gray level that transparent pixels are composited over.
+    pub(crate) background: u8,
+    /// Values of 32-bit, 16-bit and float images mapped to black and white;
+    /// the darkest and brightest value of the image if `None`. 8-bit images
+    /// and arrays ignore it.
+    pub(crate) window: Option<(f64, f64)>,
+    /// How colors become gray levels; Rec. 601 luma by default, which is
+    /// also what zxing and PIL's conversion to `L` use.
+    pub(crate) channel: Channel,
+    /// Whether the channels of color arrays are in BGR(A) order, as OpenCV
+    /// stores them, instead of RGB(A).
+    pub(crate) bgr: bool,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            background: 255,
+            window: None,
+            channel: Channel::default(),
+            bgr: false,
+        }
+    }
+}
+
+/// Named after the PIL modes.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ImageMode {
+    /// `1`: one bit per pixel, packed eight to a byte with rows byte-aligned.
+    Bilevel,
+    L,
+    LA,
+    /// `La`: luminance premultiplied by alpha.
+    La,
+    /// `P`: palette indices into the colors of the image, as RGBA.
+    P(Vec<[u8; 4]>),
+    PA(Vec<[u8; 4]>),
+    RGB,
+    RGBA,
+    /// `RGBa`: colors premultiplied by alpha.
+    RGBa,
+    RGBX,
+    CMYK,
+    YCbCr,
+    LAB,
+    HSV,
+    /// `I;16`, `I;16L`, `I;16B` and `I;16N`.
+    I16 {
+        big_endian: bool,
+    },
+    /// `I`: native endian 32-bit signed integers.
+    I32,
+    /// `F`: native endian 32-bit floats.
+    F32,
+}
+
+impl ImageMode {
+    pub(crate) const NAMES: [&str; 20] = [
+        "1", "L", "LA", "La", "P", "PA", "RGB", "RGBA", "RGBa", "RGBX", "CMYK", "YCbCr", "LAB",
+        "HSV", "I", "I;16", "I;16L", "I;16B", "I;16N", "F",
+    ];
+
+    /// The mode called `name`; `palette` is only used by `P` and `PA`.
+    pub(crate) fn new(name: &str, palette: Vec<[u8; 4]>) -> Option<Self> {
+        let mode = match name {
+            "1" => ImageMode::Bilevel,
+            "L" => ImageMode::L,
+            "LA" => ImageMode::LA,
+            "La" => ImageMode::La,
+            "P" => ImageMode::P(palette),
+            "PA" => ImageMode::PA(palette),
+            "RGB" => ImageMode::RGB,
+            "RGBA" => ImageMode::RGBA,
+            "RGBa" => ImageMode::RGBa,
+            "RGBX" => ImageMode::RGBX,
+            "CMYK" => ImageMode::CMYK,
+            "YCbCr" => ImageMode::YCbCr,
+            "LAB" => ImageMode::LAB,
+            "HSV" => ImageMode::HSV,
+            "I;16" | "I;16L" => ImageMode::I16 { big_endian: false },
+            "I;16B" => ImageMode::I16 { big_endian: true },
+            "I;16N" => ImageMode::I16 {
+                big_endian: cfg!(target_endian = "big"),
+            },
+            "I" => ImageMode::I32,
+            "F" => ImageMode::F32,
+            _ => return None,
+        };
+        Some(mode)
+    }
+
+    fn bytes_per_pixel(&self) -> usize {
+        match self {
+            ImageMode::Bilevel | ImageMode::L | ImageMode::P(_) => 1,
+            ImageMode::LA | ImageMode::La | ImageMode::PA(_) | ImageMode::I16 { .. } => 2,
+            ImageMode::RGB | ImageMode::YCbCr | ImageMode::LAB | ImageMode::HSV => 3,
+            _ => 4,
+        }
+    }
+
+    fn row_size(&self, width: usize) -> Option<usize> {
+        match self {
+            ImageMode::Bilevel => Some(width.div_ceil(8)),
+            mode => width.checked_mul(mode.bytes_per_pixel()),
+        }
+    }
+}
+
+/// The image in `data`, read in place where zxing understands the mode and
+/// otherwise converted to 8-bit luminance.
+pub(crate) fn to_image<'a>(
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    mode: &ImageMode,
+    options: &ImageOptions,
+) -> reader_core::Result<Image<'a>> {
+    // The reader applies the channel to color read in place.
+    match mode {
+        ImageMode::L => return Image::new(data, width, height, ImageFormat::Lum),
+        ImageMode::RGB => return Image::new(data, width, height, ImageFormat::RGB),
+        // zxing ignores the fourth byte like it ignores alpha
+        ImageMode::RGBX => return Image::new(data, width, height, ImageFormat::RGBA),
+        _ => {}
+    }
+    let luma = to_luma(data, width, height, mode, options)?;
+    Image::new(luma, width, height, ImageFormat::Lum)
+}
+
+/// The pixels in `data` as 8-bit luminance.
+pub(crate) fn to_luma(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    mode: &ImageMode,
+    options: &ImageOptions,
+) -> reader_core::Result<Vec<u8>> {
+    let (w, h) = (width as usize, height as usize);
+    let size = mode
+        .row_size(w)
+        .and_then(|row_size| row_size.checked_mul(h));
+    if size.is_none_or(|size| data.len() < size) {
+        return Err(reader_core::Error::InvalidInput(
+            "the image data is shorter than its mode and size require".to_string(),
+        ));
+    }
+    let background = options.background as u32;
+    let channel = options.channel;
+    let gray = |r, g, b| channel.gray(r, g, b);
+
+    let luma = match mode {
+        ImageMode::Bilevel => {
+            let row_size = w.div_ceil(8);
+            (0..h)
+                .flat_map(|y| {
+                    let row = &data[y * row_size..(y + 1) * row_size];
+                    (0..w).map(move |x| {
+                        if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                            255
+                        } else {
+                            0
+                        }
+                    })
+                })
+                .collect()
+        }
+        ImageMode::LA => pixels(data, 2, w * h, |p| over(p[0], p[1], background)),
+        ImageMode::La => pixels(data, 2, w * h, |p| {
+            over_premultiplied(p[0], p[1], background)
+        }),
+        ImageMode::P(palette) => pixels(data, 1, w * h, |p| {
+            let [r, g, b, a] = color(palette, p[0]);
+            over(gray(r, g, b), a, background)
+        }),
+        ImageMode::PA(palette) => pixels(data, 2, w * h, |p| {
+            let [r, g, b, _] = color(palette, p[0]);
+            over(gray(r, g, b), p[1], background)
+        }),
+        ImageMode::RGBA => pixels(data, 4, w * h, |p| {
+            over(gray(p[0], p[1], p[2]), p[3], background)
+        }),
+        ImageMode::RGBa => pixels(data, 4, w * h, |p| {
+            over_premultiplied(gray(p[0], p[1], p[2]), p[3], background)
+        }),
+        // Like PIL's conversion to RGB, without any color management.
+        ImageMode::CMYK => pixels(data, 4, w * h, |p| {
+            let rgb = |c: u8| 255 - c.saturating_add(p[3]);
+            gray(rgb(p[0]), rgb(p[1]), rgb(p[2]))
+        }),
+        // Converted to RGB first, so that the channel applies to them too.
+        ImageMode::YCbCr => pixels(data, 3, w * h, |p| {
+            let [r, g, b] = ycbcr_to_rgb(p[0], p[1], p[2]);
+            gray(r, g, b)
+        }),
+        ImageMode::LAB => pixels(data, 3, w * h, |p| {
+            let [r, g, b] = lab_to_rgb(p[0], p[1], p[2]);
+            gray(r, g, b)
+        }),
+        ImageMode::HSV => pixels(data, 3, w * h, |p| {
+            let [r, g, b] = hsv_to_rgb(p[0], p[1], p[2]);
+            gray(r, g, b)
+        }),
+        ImageMode::I16 { big_endian } => {
+            let big_endian = *big_endian;
+            window(data, 2, w * h, options, |p| {
+                let bytes = [p[0], p[1]];
+                if big_endian {
+                    u16::from_be_bytes(bytes) as f64
+                } else {
+                    u16::from_le_bytes(bytes) as f64
+                }
+            })
+        }
+        ImageMode::I32 => window(data, 4, w * h, options, |p| {
+            i32::from_ne_bytes([p[0], p[1], p[2], p[3]]) as f64
+        }),
+        ImageMode::F32 => window(data, 4, w * h, options, |p| {
+            f32::from_ne_bytes([p[0], p[1], p[2], p[3]]) as f64
+        }),
+        ImageMode::L => pixels(data, 1, w * h, |p| p[0]),
+        ImageMode::RGB => pixels(data, 3, w * h, |p| gray(p[0], p[1], p[2])),
+        ImageMode::RGBX => pixels(data, 4, w * h, |p| gray(p[0], p[1], p[2])),
+    };
+    Ok(luma)
+}
+
+fn pixels(data: &[u8], size: usize, count: usize, f: impl Fn(&[u8]) -> u8) -> Vec<u8> {
+    data.chunks_exact(size).take(count).map(f).collect()
+}
+
+/// Maps the values of a wide mode linearly from `window`, or the range of
+/// the image, to 0..=255. Values that aren't numbers become black.
+fn window(
+    data: &[u8],
+    size: usize,
+    count: usize,
+    options: &ImageOptions,
+    value: impl Fn(&[u8]) -> f64,
+) -> Vec<u8> {
+    window_values(|| data.chunks_exact(size).take(count).map(&value), options).collect()
+}
+
+/// Maps `values` linearly from the window of `options`, or from their own
+/// range, to 0..=255. Values that aren't numbers become black. If all the
+/// numbers are equal there is no range to map them from, and they become the
+/// background instead.
+///
+/// `values` is called twice when the range has to be found, so that the
+/// values need not be held in memory.
+pub(crate) fn window_values<I: Iterator<Item = f64>>(
+    values: impl Fn() -> I,
+    options: &ImageOptions,
+) -> impl Iterator<Item = u8> {
+    let (low, high) = options.window.unwrap_or_else(|| {
+        values()
+            .filter(|v| v.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), v| {
+                (low.min(v), high.max(v))
+            })
+    });
+    // An explicit window is checked to be non-empty when the options are
+    // parsed, so only the range of a uniform image ends up here.
+    let uniform = high <= low;
+    let scale = if uniform { 0.0 } else { 255.0 / (high - low) };
+    let background = options.background;
+    values().map(move |v| {
+        if uniform {
+            match v {
+                v if v == low => background,
+                v if v > low => 255,
+                _ => 0,
+            }
+        } else {
+            // `as` saturates, and maps NaN to 0
+            ((v - low) * scale).round() as u8
+        }
+    })
+}
+
+/// Like PIL's conversion from `YCbCr`, with the JPEG (full range BT.601)
+/// equations.
+fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> [u8; 3] {
+    let (y, cb, cr) = (y as f32, cb as f32 - 128.0, cr as f32 - 128.0);
+    // `as` saturates
+    [
+        (y + 1.402 * cr).round() as u8,
+        (y - 0.344136 * cb - 0.714136 * cr).round() as u8,
+        (y + 1.772 * cb).round() as u8,
+    ]
+}
+
+/// CIE L*a*b* as PIL stores it, L* scaled to 0..=255 and a* and b* offset
+/// by 128, to sRGB under a D65 white point.
+fn lab_to_rgb(l: u8, a: u8, b: u8) -> [u8; 3] {
+    let l = l as f32 * 100.0 / 255.0;
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + (a as f32 - 128.0) / 500.0;
+    let fz = fy - (b as f32 - 128.0) / 200.0;
+    let inverse = |t: f32| {
+        if t > 6.0 / 29.0 {
+            t * t * t
+        } else {
+            3.0 * (6.0f32 / 29.0).powi(2) * (t - 4.0 / 29.0)
+        }
+    };
+    let (x, y, z) = (0.95047 * inverse(fx), inverse(fy), 1.08883 * inverse(fz));
+    let linear = [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ];
+    linear.map(|c| {
+        let c = if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        // `as` saturates, and maps NaN to 0
+        (c * 255.0).round() as u8
+    })
+}
+
+/// Like PIL's conversion from `HSV`, with all three components in 0..=255.
+fn hsv_to_rgb(h: u8, s: u8, v: u8) -> [u8; 3] {
+    let (s, v) = (s as f32 / 255.0, v as f32);
+    let sector = h as f32 / 255.0 * 6.0;
+    let f = sector.fract();
+    let (p, q, t) = (v * (1.0 - s), v * (1.0 - s * f), v * (1.0 - s * (1.0 - f)));
+    let [r, g, b] = match sector as u8 {
+        0 | 6 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    };
+    [r, g, b].map(|c| c.round() as u8)
+}
+
+fn color(palette: &[[u8; 4]], index: u8) -> [u8; 4] {
+    palette
+        .get(index as usize)
+        .copied()
+        .unwrap_or([index, index, index, 255])
+}
+
+pub(crate) fn over(value: u8, alpha: u8, background: u32) -> u8 {
+    let alpha = alpha as u32;
+    ((value as u32 * alpha + background * (255 - alpha) + 127) / 255) as u8
+}
+
+fn over_premultiplied(value: u8, alpha: u8, background: u32) -> u8 {
+    let alpha = alpha as u32;
+    (value as u32 + (background * (255 - alpha) + 127) / 255).min(255) as u8
+}
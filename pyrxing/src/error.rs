@@ -63,6 +63,7 @@ impl From<PyImageError> for PyErr {
 pub(crate) enum ImageError {
     Decode(image::ImageError),
     UnsupportedMode(String),
+    Invalid(String),
 }
 
 #[derive(Debug)]
@@ -97,6 +98,15 @@ impl From<image::ImageError> for Error {
     }
 }
 
+impl From<reader_core::Error> for Error {
+    fn from(value: reader_core::Error) -> Self {
+        match value {
+            reader_core::Error::InvalidInput(e) => Self::Image(ImageError::Invalid(e)),
+            e => Self::Decode(e.to_string()),
+        }
+    }
+}
+
 impl From<Error> for PyErr {
     fn from(value: Error) -> Self {
         use Error::*;
@@ -105,6 +115,7 @@ impl From<Error> for PyErr {
             Image(e) => match e {
                 ImageError::Decode(e) => PyErr::new::<PyImageError, _>(e.to_string()),
                 ImageError::UnsupportedMode(e) => PyErr::new::<PyImageError, _>(e.to_string()),
+                ImageError::Invalid(e) => PyErr::new::<PyImageError, _>(e),
             },
             Io(e) => match e.kind() {
                 std::io::ErrorKind::NotFound => pyo3::exceptions::PyFileNotFoundError::new_err(e),
@@ -1,14 +1,22 @@
+mod convert;
 mod error;
 
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader, Cursor, Seek};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 
-use image::ImageReader;
+use image::{ColorType, DynamicImage, GrayAlphaImage, ImageReader, RgbaImage};
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::panic::PanicException;
 use pyo3::prelude::*;
 use pyo3::pybacked::PyBackedStr;
-use pyo3::types::PyBytes;
+use pyo3::types::{PyBytes, PyDict, PyIterator, PyString};
 
-use reader_core::{self, GrayImage};
+use reader_core::{self, Channel, DecodeOptions, Image, ImageFormat};
+
+use crate::convert::{ImageMode, ImageOptions};
 
 type Result<T> = std::result::Result<T, error::Error>;
 
@@ -28,14 +36,113 @@ impl Point {
     }
 }
 
+#[pyclass(module = "pyrxing", skip_from_py_object)]
+#[derive(Clone)]
+struct BitMatrix {
+    #[pyo3(get)]
+    width: usize,
+    #[pyo3(get)]
+    height: usize,
+    #[pyo3(get)]
+    row_stride: usize,
+    bits: Vec<u8>,
+}
+
+#[pymethods]
+impl BitMatrix {
+    #[getter]
+    fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.bits)
+    }
+
+    fn to_list(&self) -> Vec<Vec<bool>> {
+        self.bits
+            .chunks_exact(self.row_stride)
+            .map(|row| {
+                (0..self.width)
+                    .map(|x| row[x / 8] & (0x80 >> (x % 8)) != 0)
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "BitMatrix(width={}, height={})",
+            self.width, self.height
+        ))
+    }
+}
+
+impl From<&reader_core::BitMatrix> for BitMatrix {
+    fn from(value: &reader_core::BitMatrix) -> Self {
+        Self {
+            width: value.width(),
+            height: value.height(),
+            row_stride: value.row_stride(),
+            bits: value.as_bytes().to_vec(),
+        }
+    }
+}
+
 #[pyclass(module = "pyrxing")]
 struct DecodeResult {
     #[pyo3(get)]
     text: String,
+    bytes: Vec<u8>,
+    bytes_eci: Vec<u8>,
+    #[pyo3(get)]
+    main_text: String,
+    #[pyo3(get)]
+    add_on: Option<String>,
     #[pyo3(get)]
     points: Vec<Point>,
     #[pyo3(get)]
     format: String,
+    #[pyo3(get)]
+    symbology_identifier: String,
+    #[pyo3(get)]
+    content_type: String,
+    #[pyo3(get)]
+    ec_level: Option<String>,
+    #[pyo3(get)]
+    version: Option<String>,
+    #[pyo3(get)]
+    reader_init: bool,
+    #[pyo3(get)]
+    orientation: i32,
+    #[pyo3(get)]
+    is_mirrored: bool,
+    #[pyo3(get)]
+    is_inverted: bool,
+    #[pyo3(get)]
+    is_valid: bool,
+    #[pyo3(get)]
+    error_kind: Option<String>,
+    #[pyo3(get)]
+    error_message: Option<String>,
+    #[pyo3(get)]
+    sequence_size: Option<usize>,
+    #[pyo3(get)]
+    sequence_index: Option<usize>,
+    #[pyo3(get)]
+    sequence_id: Option<String>,
+    segment: Option<reader_core::Segment>,
+    #[pyo3(get)]
+    symbol: Option<BitMatrix>,
+}
+
+#[pymethods]
+impl DecodeResult {
+    #[getter]
+    fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.bytes)
+    }
+
+    #[getter]
+    fn bytes_eci<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.bytes_eci)
+    }
 }
 
 impl TryFrom<reader_core::DecodeResult> for DecodeResult {
@@ -44,61 +151,354 @@ impl TryFrom<reader_core::DecodeResult> for DecodeResult {
     fn try_from(value: reader_core::DecodeResult) -> std::result::Result<Self, Self::Error> {
         Ok(Self {
             text: value.text().to_owned(),
+            bytes: value.bytes().to_vec(),
+            bytes_eci: value.bytes_eci().to_vec(),
+            main_text: value.main_text().to_owned(),
+            add_on: value.add_on().map(str::to_owned),
             points: value
                 .points()
                 .iter()
                 .map(|p| Point { x: p.x(), y: p.y() })
                 .collect::<Vec<_>>(),
-            format: format!(
-                "{}",
-                value
-                    .format()
-                    .map_err(|e| error::Error::Decode(e.to_string()))?
-            ),
+            format: format!("{}", value.format().map_err(error::Error::from)?),
+            symbology_identifier: value.symbology_identifier().to_owned(),
+            content_type: value.content_type().to_string(),
+            ec_level: value.ec_level().map(str::to_owned),
+            version: value.version().map(str::to_owned),
+            reader_init: value.reader_init(),
+            orientation: value.orientation(),
+            is_mirrored: value.is_mirrored(),
+            is_inverted: value.is_inverted(),
+            is_valid: value.is_valid(),
+            error_kind: value.error().map(|e| {
+                match e {
+                    reader_core::BarcodeError::Checksum(_) => "Checksum",
+                    reader_core::BarcodeError::Format(_) => "Format",
+                    reader_core::BarcodeError::Unsupported(_) => "Unsupported",
+                }
+                .to_owned()
+            }),
+            error_message: value.error().map(|e| e.message().to_owned()),
+            sequence_size: value.sequence_size(),
+            sequence_index: value.sequence_index(),
+            sequence_id: value.sequence_id().map(str::to_owned),
+            segment: value.segment(),
+            symbol: value.symbol().map(BitMatrix::from),
         })
     }
 }
 
+#[pyclass(module = "pyrxing")]
+struct StructuredMessage {
+    #[pyo3(get)]
+    format: String,
+    #[pyo3(get)]
+    id: String,
+    #[pyo3(get)]
+    size: Option<usize>,
+    #[pyo3(get)]
+    text: String,
+    bytes: Vec<u8>,
+    #[pyo3(get)]
+    missing: Vec<usize>,
+    #[pyo3(get)]
+    is_complete: bool,
+}
+
+#[pymethods]
+impl StructuredMessage {
+    #[getter]
+    fn bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.bytes)
+    }
+}
+
+impl From<reader_core::StructuredMessage> for StructuredMessage {
+    fn from(value: reader_core::StructuredMessage) -> Self {
+        Self {
+            format: value.format().to_string(),
+            id: value.id().to_owned(),
+            size: value.size(),
+            text: value.text(),
+            bytes: value.bytes(),
+            missing: value.missing(),
+            is_complete: value.is_complete(),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ImageSource<'a> {
-    Path(PyBackedStr),
+    Path(PathBuf),
+    /// An encoded image file held in memory.
+    Encoded(PyBuffer<u8>),
     ImageProtocolData {
         data: Vec<u8>,
         width: u32,
         height: u32,
+        mode: ImageMode,
     },
     ImageProtocolDataView {
         _holder: Bound<'a, PyAny>,
-        data: Bound<'a, PyAny>,
+        data: Bound<'a, PyBytes>,
         width: u32,
         height: u32,
     },
+    Array(ArraySource),
 }
 
-impl<'a, 'b: 'a> TryInto<GrayImage<'a>> for &'a ImageSource<'b> {
-    type Error = error::Error;
+/// What is needed from an `ImageSource` to produce the image, without any
+/// Python objects, so that decoding can run with the GIL released.
+enum ImageData<'a> {
+    Path(&'a Path, &'a ImageOptions),
+    Encoded(&'a [u8], &'a ImageOptions),
+    Gray {
+        data: &'a [u8],
+        width: u32,
+        height: u32,
+    },
+    Pixels {
+        data: &'a [u8],
+        width: u32,
+        height: u32,
+        mode: &'a ImageMode,
+        options: &'a ImageOptions,
+    },
+    Array(&'a ArraySource, &'a ImageOptions),
+}
 
-    fn try_into(self) -> std::result::Result<GrayImage<'a>, Self::Error> {
-        match self {
-            ImageSource::Path(path) => {
-                let file = File::open(&**path)?;
-                let file_size = file.metadata()?.len();
+/// A 2D grayscale or HxWx3/HxWx4 color array of `uint8`, e.g. a NumPy array,
+/// exposed through the buffer protocol or `__array_interface__` and read in
+/// place.
+#[derive(Debug)]
+struct ArraySource {
+    /// Keeps the memory `ptr` points into alive; never read otherwise.
+    _buffer: PyBuffer<u8>,
+    ptr: *const u8,
+    width: u32,
+    height: u32,
+    channels: usize,
+    /// Byte strides between rows, columns and channels; may be negative for
+    /// arrays from the buffer protocol.
+    strides: [isize; 3],
+}
 
-                let sizes = [file_size as usize, 10_000_000];
-                let capacity = sizes.iter().min().unwrap();
+// SAFETY: `ptr` points into memory kept alive by `_buffer`, which may be sent
+// to and shared between threads, and the memory is only ever read.
+unsafe impl Send for ArraySource {}
+unsafe impl Sync for ArraySource {}
 
-                let buf_reader = BufReader::with_capacity(*capacity, file);
-                let reader = ImageReader::new(buf_reader).with_guessed_format()?;
-                let image_buffer = reader.decode()?.to_luma8();
-                let width = image_buffer.width();
-                let height = image_buffer.height();
-                Ok(GrayImage::new(image_buffer.into_raw(), width, height))
+impl ArraySource {
+    fn from_buffer(buffer: PyBuffer<u8>) -> PyResult<Self> {
+        if buffer.suboffsets().is_some() {
+            return Err(PyValueError::new_err(
+                "arrays with indirect (suboffset) memory are not supported",
+            ));
+        }
+        let ptr = buffer.buf_ptr() as *const u8;
+        let (shape, strides) = (buffer.shape().to_vec(), buffer.strides().to_vec());
+        Self::new(buffer, ptr, &shape, Some(&strides))
+    }
+
+    fn from_array_interface(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let interface = obj.getattr("__array_interface__")?;
+        let type_str = interface.get_item("typestr")?.extract::<PyBackedStr>()?;
+        if type_str.get(1..) != Some("u1") {
+            return Err(PyValueError::new_err(format!(
+                "arrays must have dtype uint8, got typestr `{}`",
+                &*type_str
+            )));
+        }
+        let shape = interface.get_item("shape")?.extract::<Vec<usize>>()?;
+        let strides = match interface.get_item("strides") {
+            Ok(strides) if !strides.is_none() => Some(strides.extract::<Vec<isize>>()?),
+            _ => None,
+        };
+        if strides.iter().flatten().any(|&stride| stride < 0) {
+            return Err(PyValueError::new_err(
+                "negative strides are only supported through the buffer protocol",
+            ));
+        }
+
+        // A bare `(address, readonly)` pointer cannot be checked against the
+        // size of the memory it points to, so only buffers are read.
+        let data = interface.get_item("data")?;
+        let buffer = PyBuffer::<u8>::get(&data)
+            .ok()
+            .filter(|buffer| buffer.is_c_contiguous())
+            .ok_or_else(|| {
+                PyValueError::new_err(
+                    "the data of `__array_interface__` must be a contiguous buffer",
+                )
+            })?;
+        let offset = match interface.get_item("offset") {
+            Ok(offset) => offset.extract::<usize>()?,
+            Err(_) => 0,
+        };
+        let (ptr, len) = (buffer.buf_ptr() as *const u8, buffer.len_bytes());
+        let source = Self::new(buffer, ptr.wrapping_add(offset), &shape, strides.as_deref())?;
+        if !source.fits_in(offset, len) {
+            return Err(PyValueError::new_err(
+                "the shape and strides of the array exceed its data",
+            ));
+        }
+        Ok(source)
+    }
+
+    fn new(
+        buffer: PyBuffer<u8>,
+        ptr: *const u8,
+        shape: &[usize],
+        strides: Option<&[isize]>,
+    ) -> PyResult<Self> {
+        let (height, width, channels) = match *shape {
+            [height, width] => (height, width, 1),
+            [height, width, channels @ (1 | 3 | 4)] => (height, width, channels),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "expected a 2D grayscale or an HxWx3/HxWx4 color array, got shape {:?}",
+                    shape
+                )));
+            }
+        };
+        if width == 0 || height == 0 {
+            return Err(PyValueError::new_err("the array is empty"));
+        }
+        let too_large = || PyValueError::new_err("the array is too large");
+        let strides = match strides {
+            Some(&[row, column]) => [row, column, 1],
+            Some(&[row, column, channel]) => [row, column, channel],
+            Some(strides) => {
+                return Err(PyValueError::new_err(format!(
+                    "the strides {:?} do not match the shape {:?}",
+                    strides, shape
+                )));
+            }
+            // C-contiguous
+            None => {
+                let row = width.checked_mul(channels).ok_or_else(too_large)?;
+                [
+                    isize::try_from(row).map_err(|_| too_large())?,
+                    channels as isize,
+                    1,
+                ]
+            }
+        };
+
+        Ok(Self {
+            _buffer: buffer,
+            ptr,
+            width: u32::try_from(width).map_err(|_| too_large())?,
+            height: u32::try_from(height).map_err(|_| too_large())?,
+            channels,
+            strides,
+        })
+    }
+
+    /// Whether every pixel lies within `len` bytes of data starting `offset`
+    /// bytes before `ptr`, for arrays with non-negative strides.
+    fn fits_in(&self, offset: usize, len: usize) -> bool {
+        let last = [
+            self.height as usize - 1,
+            self.width as usize - 1,
+            self.channels - 1,
+        ];
+        self.strides
+            .iter()
+            .zip(last)
+            .try_fold(offset, |end, (&stride, last)| {
+                end.checked_add(usize::try_from(stride).ok()?.checked_mul(last)?)
+            })
+            .is_some_and(|end| end < len)
+    }
+
+    /// The array as an image: in place if its pixels can be described by a
+    /// row and a pixel stride, otherwise with colors turned into gray levels
+    /// by the channel of `options`. Channels are taken as RGB(A), or BGR(A) if
+    /// `options` says so; transparent pixels are composited over the
+    /// background, so arrays with alpha are always copied.
+    fn to_image(&self, options: &ImageOptions) -> Result<Image<'_>> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let [row, column, stride] = self.strides;
+        let channel = options.channel;
+        let (red, blue) = if options.bgr { (2, 0) } else { (0, 2) };
+
+        if row >= 0 && column >= 0 && (self.channels == 1 || self.channels == 3 && stride == 1) {
+            let format = match self.channels {
+                1 => ImageFormat::Lum,
+                _ if options.bgr => ImageFormat::BGR,
+                _ => ImageFormat::RGB,
+            };
+            let (row, column) = (row as usize, column as usize);
+            let len = (height - 1) * row + (width - 1) * column + self.channels;
+            // SAFETY: with non-negative strides the last channel of the last
+            // pixel is the furthest byte of the array from `ptr`.
+            let data = unsafe { std::slice::from_raw_parts(self.ptr, len) };
+            // Overlapping pixels or rows, e.g. broadcast arrays, are rejected
+            // here and copied below.
+            if let Ok(image) =
+                Image::with_strides(data, self.width, self.height, format, row, column)
+            {
+                return Ok(image);
             }
+        }
+
+        let mut luma = Vec::with_capacity(width * height);
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                // SAFETY: the offsets of all pixels and channels lie within
+                // the array, as described by its shape and strides.
+                let value = unsafe {
+                    let pixel = self.ptr.offset(y * row + x * column);
+                    if self.channels < 3 {
+                        *pixel
+                    } else {
+                        let r = *pixel.offset(red * stride);
+                        let g = *pixel.offset(stride);
+                        let b = *pixel.offset(blue * stride);
+                        let gray = channel.gray(r, g, b);
+                        if self.channels == 4 {
+                            convert::over(
+                                gray,
+                                *pixel.offset(3 * stride),
+                                options.background as u32,
+                            )
+                        } else {
+                            gray
+                        }
+                    }
+                };
+                luma.push(value);
+            }
+        }
+        Ok(Image::new(luma, self.width, self.height, ImageFormat::Lum)?)
+    }
+}
+
+impl ImageSource<'_> {
+    fn data<'a>(&'a self, options: &'a ImageOptions) -> ImageData<'a> {
+        match self {
+            ImageSource::Path(path) => ImageData::Path(path, options),
+            // SAFETY: the buffer is C-contiguous (checked when it was taken) and
+            // stays exported for as long as `self` lives.
+            ImageSource::Encoded(buffer) => ImageData::Encoded(
+                unsafe {
+                    std::slice::from_raw_parts(buffer.buf_ptr() as *const u8, buffer.len_bytes())
+                },
+                options,
+            ),
             ImageSource::ImageProtocolData {
                 data,
                 width,
                 height,
-            } => Ok(GrayImage::new(data, *width, *height)),
+                mode,
+            } => ImageData::Pixels {
+                data,
+                width: *width,
+                height: *height,
+                mode,
+                options,
+            },
             ImageSource::ImageProtocolDataView {
                 // Holds the __array_interface__ object to maintain its reference count.
                 // Python's __array_interface__ property may creates temporary view objects
@@ -107,20 +507,259 @@ impl<'a, 'b: 'a> TryInto<GrayImage<'a>> for &'a ImageSource<'b> {
                 data,
                 width,
                 height,
-            } => {
-                let py_bytes = data.cast::<PyBytes>().unwrap().as_bytes();
-                Ok(GrayImage::new(py_bytes, *width, *height))
+            } => ImageData::Gray {
+                data: data.as_bytes(),
+                width: *width,
+                height: *height,
+            },
+            ImageSource::Array(array) => ImageData::Array(array, options),
+        }
+    }
+}
+
+/// An `ImageSource` that no longer borrows the GIL, so it can be moved to the
+/// worker thread of an async read and bound again there.
+enum UnboundImageSource {
+    Path(PathBuf),
+    Encoded(PyBuffer<u8>),
+    ImageProtocolData {
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        mode: ImageMode,
+    },
+    ImageProtocolDataView {
+        holder: Py<PyAny>,
+        data: Py<PyBytes>,
+        width: u32,
+        height: u32,
+    },
+    Array(ArraySource),
+}
+
+impl ImageSource<'_> {
+    fn unbind(self) -> UnboundImageSource {
+        match self {
+            ImageSource::Path(path) => UnboundImageSource::Path(path),
+            ImageSource::Encoded(buffer) => UnboundImageSource::Encoded(buffer),
+            ImageSource::ImageProtocolData {
+                data,
+                width,
+                height,
+                mode,
+            } => UnboundImageSource::ImageProtocolData {
+                data,
+                width,
+                height,
+                mode,
+            },
+            ImageSource::ImageProtocolDataView {
+                _holder,
+                data,
+                width,
+                height,
+            } => UnboundImageSource::ImageProtocolDataView {
+                holder: _holder.unbind(),
+                data: data.unbind(),
+                width,
+                height,
+            },
+            ImageSource::Array(array) => UnboundImageSource::Array(array),
+        }
+    }
+}
+
+impl UnboundImageSource {
+    fn bind(self, py: Python<'_>) -> ImageSource<'_> {
+        match self {
+            UnboundImageSource::Path(path) => ImageSource::Path(path),
+            UnboundImageSource::Encoded(buffer) => ImageSource::Encoded(buffer),
+            UnboundImageSource::ImageProtocolData {
+                data,
+                width,
+                height,
+                mode,
+            } => ImageSource::ImageProtocolData {
+                data,
+                width,
+                height,
+                mode,
+            },
+            UnboundImageSource::ImageProtocolDataView {
+                holder,
+                data,
+                width,
+                height,
+            } => ImageSource::ImageProtocolDataView {
+                _holder: holder.into_bound(py),
+                data: data.into_bound(py),
+                width,
+                height,
+            },
+            UnboundImageSource::Array(array) => ImageSource::Array(array),
+        }
+    }
+}
+
+impl<'a> TryFrom<ImageData<'a>> for Image<'a> {
+    type Error = error::Error;
+
+    fn try_from(value: ImageData<'a>) -> std::result::Result<Self, Self::Error> {
+        match value {
+            ImageData::Path(path, options) => {
+                let file = File::open(path)?;
+                let file_size = file.metadata()?.len();
+
+                let sizes = [file_size as usize, 10_000_000];
+                let capacity = sizes.iter().min().unwrap();
+
+                let buf_reader = BufReader::with_capacity(*capacity, file);
+                decode_image_file(buf_reader, options)
             }
+            ImageData::Encoded(data, options) => decode_image_file(Cursor::new(data), options),
+            ImageData::Gray {
+                data,
+                width,
+                height,
+            } => Ok(Image::new(data, width, height, ImageFormat::Lum)?),
+            ImageData::Pixels {
+                data,
+                width,
+                height,
+                mode,
+                options,
+            } => Ok(convert::to_image(data, width, height, mode, options)?),
+            ImageData::Array(array, options) => array.to_image(options),
+        }
+    }
+}
+
+/// Decodes an image file of any supported format, guessed from its contents.
+/// Transparent pixels are composited over the background.
+fn decode_image_file(
+    reader: impl BufRead + Seek,
+    options: &ImageOptions,
+) -> Result<Image<'static>> {
+    let reader = ImageReader::new(reader).with_guessed_format()?;
+    let image = window_image(reader.decode()?, options);
+    let width = image.width();
+    let height = image.height();
+    // Colors go through `channel` like those of every other source, rather
+    // than the Rec. 709 luma of the image crate.
+    let color = image.color().has_color();
+    let data = match (color, image.color().has_alpha()) {
+        (true, true) => {
+            let data = image.to_rgba8().into_raw();
+            convert::to_luma(&data, width, height, &ImageMode::RGBA, options)?
+        }
+        (true, false) => {
+            let data = image.to_rgb8().into_raw();
+            convert::to_luma(&data, width, height, &ImageMode::RGB, options)?
+        }
+        (false, true) => {
+            let data = image.to_luma_alpha8().into_raw();
+            convert::to_luma(&data, width, height, &ImageMode::LA, options)?
+        }
+        (false, false) => image.to_luma8().into_raw(),
+    };
+    Ok(Image::new(data, width, height, ImageFormat::Lum)?)
+}
+
+/// `image` with 16-bit and float samples mapped to 8 bits through the window
+/// of `options`, like PIL's wide modes. The color channels share one window,
+/// alpha is only rescaled. 8-bit images are returned as they are.
+fn window_image(image: DynamicImage, options: &ImageOptions) -> DynamicImage {
+    let color = image.color();
+    if color.bytes_per_pixel() == color.channel_count() {
+        return image;
+    }
+    let (width, height) = (image.width(), image.height());
+    let data = match color {
+        ColorType::Rgb32F | ColorType::Rgba32F => {
+            window_samples(&image.into_rgba32f(), 1.0, color.has_color(), options)
+        }
+        _ => window_samples(&image.into_rgba16(), 65535.0, color.has_color(), options),
+    };
+    let image = if color.has_color() {
+        RgbaImage::from_raw(width, height, data).map(DynamicImage::ImageRgba8)
+    } else {
+        GrayAlphaImage::from_raw(width, height, data).map(DynamicImage::ImageLumaA8)
+    };
+    image.expect("one output pixel per input pixel")
+}
+
+/// RGBA samples windowed to RGBA8, or to LA8 if `color` is false, in which
+/// case the three color samples of a pixel are equal.
+fn window_samples<T: Copy + Into<f64>>(
+    rgba: &[T],
+    alpha_max: f64,
+    color: bool,
+    options: &ImageOptions,
+) -> Vec<u8> {
+    let channels = if color { 3 } else { 1 };
+    let mut levels = convert::window_values(
+        || {
+            rgba.chunks_exact(4)
+                .flat_map(move |p| p[..channels].iter().map(|&v| v.into()))
+        },
+        options,
+    );
+    let mut data = Vec::with_capacity(rgba.len() / 4 * (channels + 1));
+    for pixel in rgba.chunks_exact(4) {
+        data.extend(levels.by_ref().take(channels));
+        data.push((pixel[3].into() / alpha_max * 255.0).round() as u8);
+    }
+    data
+}
+
+/// Takes the encoded image file held by a 1-D buffer, e.g. `bytes`.
+fn encoded_image(buffer: PyBuffer<u8>) -> Result<ImageSource<'static>> {
+    if !buffer.is_c_contiguous() {
+        return Err(error::Error::Python(PyValueError::new_err(
+            "encoded image data must be contiguous",
+        )));
+    }
+    Ok(ImageSource::Encoded(buffer))
+}
+
+/// The palette of a `P` or `PA` image as RGBA, with the transparency of a `P`
+/// image applied. Images without `getpalette()` are read as grayscale.
+fn palette(obj: &Bound<'_, PyAny>) -> PyResult<Vec<[u8; 4]>> {
+    let colors = if obj.hasattr("getpalette")? {
+        obj.call_method0("getpalette")?
+            .extract::<Option<Vec<u8>>>()?
+    } else {
+        None
+    };
+    let colors = colors.unwrap_or_else(|| (0..=255).flat_map(|v| [v, v, v]).collect());
+    let mut palette = colors
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2], 255])
+        .collect::<Vec<_>>();
+    palette.resize(256, [0, 0, 0, 255]);
+
+    if !obj.hasattr("info")? {
+        return Ok(palette);
+    }
+    let transparency = obj
+        .getattr("info")?
+        .call_method1("get", ("transparency",))?;
+    if let Ok(index) = transparency.extract::<u8>() {
+        palette[index as usize][3] = 0;
+    } else if let Ok(alphas) = transparency.extract::<Vec<u8>>() {
+        for (color, alpha) in palette.iter_mut().zip(alphas) {
+            color[3] = alpha;
         }
     }
+    Ok(palette)
 }
 
 fn get_image_source<'a>(obj: &Bound<'a, PyAny>) -> Result<ImageSource<'a>> {
     let type_obj = obj.get_type();
     let type_name = type_obj.name()?;
 
-    if type_name == "str" {
-        Ok(ImageSource::Path(obj.extract::<PyBackedStr>()?))
+    if type_name == "str" || obj.hasattr("__fspath__")? {
+        Ok(ImageSource::Path(obj.extract::<PathBuf>()?))
     } else {
         let mut conform_to_image_protocol = true;
         if !obj.hasattr("mode")?
@@ -140,46 +779,55 @@ fn get_image_source<'a>(obj: &Bound<'a, PyAny>) -> Result<ImageSource<'a>> {
             }
         }
         if !conform_to_image_protocol {
+            if obj.hasattr("read")? && obj.getattr("read")?.is_callable() {
+                let data = obj.call_method0("read")?;
+                let buffer = PyBuffer::<u8>::get(&data).map_err(|_| {
+                    PyValueError::new_err("file-like objects must be opened in binary mode")
+                })?;
+                return encoded_image(buffer);
+            }
+            if let Ok(buffer) = PyBuffer::<u8>::get(obj) {
+                if buffer.dimensions() == 1 {
+                    return encoded_image(buffer);
+                }
+                return Ok(ImageSource::Array(ArraySource::from_buffer(buffer)?));
+            }
+            if obj.hasattr("__array_interface__")? {
+                return Ok(ImageSource::Array(ArraySource::from_array_interface(obj)?));
+            }
             return Err(error::Error::Python(
                 pyo3::exceptions::PyValueError::new_err(
-                    "value must be either str or conform to ImageProtocol",
+                    "value must be a path, encoded image bytes, a binary file-like object, conform to ImageProtocol or be a uint8 array",
                 ),
             ));
         }
 
         let mode = obj.getattr("mode")?.extract::<PyBackedStr>()?;
-
-        let mut converted_opt = None;
-        match &*mode {
-            "L" => {}
-            "RGB" | "RGBA" | "P" | "1" => {
-                converted_opt = Some(obj.call_method1("convert", ("L",))?);
-            }
-            _ => {
-                let message = format!(
-                    "The specified image has an unsupported mode({}). Only grayscale, RGB(A), or palette mode images are supported.",
-                    mode
-                );
-                return Err(error::ImageError::UnsupportedMode(message).into());
-            }
-        }
-
-        let target_obj: &Bound<'a, PyAny> = match &converted_opt {
-            Some(v) => {
-                v.call_method0("load")?;
-                v
-            }
-            None => obj,
+        let palette = match &*mode {
+            "P" | "PA" => palette(obj)?,
+            _ => Vec::new(),
+        };
+        let Some(mode) = ImageMode::new(&mode, palette) else {
+            let message = format!(
+                "The specified image has an unsupported mode({}). Supported modes are {}.",
+                mode,
+                ImageMode::NAMES.join(", ")
+            );
+            return Err(error::ImageError::UnsupportedMode(message).into());
         };
 
-        let width = target_obj.getattr("width")?.extract::<u32>()?;
-        let height = target_obj.getattr("height")?.extract::<u32>()?;
+        obj.call_method0("load")?;
+        let width = obj.getattr("width")?.extract::<u32>()?;
+        let height = obj.getattr("height")?.extract::<u32>()?;
 
-        if target_obj.hasattr("__array_interface__")? {
-            let ai = target_obj.getattr("__array_interface__")?;
+        if mode == ImageMode::L && obj.hasattr("__array_interface__")? {
+            let ai = obj.getattr("__array_interface__")?;
             let type_str = ai.get_item("typestr")?.extract::<PyBackedStr>()?;
-            if type_str == "|u1" {
-                let data = ai.get_item("data")?;
+            // Other data, e.g. a pointer or a buffer that may change, is
+            // copied with `tobytes()` below.
+            if type_str == "|u1"
+                && let Ok(data) = ai.get_item("data")?.cast_into::<PyBytes>()
+            {
                 return Ok(ImageSource::ImageProtocolDataView {
                     _holder: ai,
                     data,
@@ -189,17 +837,18 @@ fn get_image_source<'a>(obj: &Bound<'a, PyAny>) -> Result<ImageSource<'a>> {
             }
         }
 
-        let data = target_obj.call_method0("tobytes")?.extract::<Vec<u8>>()?;
+        let data = obj.call_method0("tobytes")?.extract::<Vec<u8>>()?;
         Ok(ImageSource::ImageProtocolData {
             data,
             width,
             height,
+            mode,
         })
     }
 }
 
 enum Decoded {
-    Single(Option<DecodeResult>),
+    Single(Option<Box<DecodeResult>>),
     Multi(Vec<DecodeResult>),
 }
 
@@ -208,53 +857,267 @@ struct _BarcodeFormat(String);
 impl TryFrom<_BarcodeFormat> for reader_core::BarcodeFormat {
     type Error = String;
     fn try_from(value: _BarcodeFormat) -> std::result::Result<Self, Self::Error> {
-        use reader_core::BarcodeFormat as BF;
-        let v = match value.0.as_str() {
-            "Aztec" => BF::Aztec,
-            "Codabar" => BF::Codabar,
-            "Code39" => BF::Code39,
-            "Code93" => BF::Code93,
-            "Code128" => BF::Code128,
-            "DataBar" => BF::DataBar,
-            "DataBarExpanded" => BF::DataBarExp,
-            "DataBarExpandedStacked" => BF::DataBarExpStk,
-            "DataBarLimited" => BF::DataBarLtd,
-            "DataBarOmni" => BF::DataBarOmni,
-            "DataBarStacked" => BF::DataBarStk,
-            "DataBarStackedOmni" => BF::DataBarStkOmni,
-            "DataMatrix" => BF::DataMatrix,
-            "EAN8" => BF::EAN8,
-            "EAN13" => BF::EAN13,
-            "ITF" => BF::ITF,
-            "MaxiCode" => BF::MaxiCode,
-            "PDF417" => BF::PDF417,
-            "QRCode" => BF::QRCode,
-            "UPCA" => BF::UPCA,
-            "UPCE" => BF::UPCE,
-            "MicroQRCode" => BF::MicroQRCode,
-            "RMQRCode" => BF::RMQRCode,
-            "DXFilmEdge" => BF::DXFilmEdge,
+        value.0.parse().map_err(|_| {
+            let names = reader_core::BarcodeFormat::ALL
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>();
+            format!(
+                "`{}` is not a supported barcode format, expected one of: {}",
+                value.0,
+                names.join(", ")
+            )
+        })
+    }
+}
+
+/// `formats` argument: either a `BarcodeFormats` flag (any int) or a list of names.
+/// A flag without members reads nothing, an empty list every format.
+#[derive(FromPyObject)]
+enum _BarcodeFormats {
+    Flags(u64),
+    Names(Vec<String>),
+}
+
+impl TryFrom<_BarcodeFormats> for reader_core::BarcodeFormats {
+    type Error = String;
+    fn try_from(value: _BarcodeFormats) -> std::result::Result<Self, Self::Error> {
+        match value {
+            _BarcodeFormats::Flags(bits) => reader_core::BarcodeFormats::from_bits(bits)
+                .ok_or_else(|| format!("`{:#x}` is not a valid BarcodeFormats value", bits)),
+            _BarcodeFormats::Names(names) if names.is_empty() => {
+                Ok(reader_core::BarcodeFormats::ANY)
+            }
+            _BarcodeFormats::Names(names) => names
+                .into_iter()
+                .map(|bf| reader_core::BarcodeFormat::try_from(_BarcodeFormat(bf)))
+                .collect(),
+        }
+    }
+}
+
+/// Builds `pyrxing.BarcodeFormats`, an `enum.IntFlag` with one member per
+/// format plus the named groups, using the same bits as `reader_core`.
+fn barcode_formats_flag<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+    let mut members = reader_core::BarcodeFormat::ALL
+        .iter()
+        .map(|f| (f.to_string(), reader_core::BarcodeFormats::from(*f).bits()))
+        .collect::<Vec<_>>();
+    members.extend([
+        (
+            "LinearCodes".to_owned(),
+            reader_core::BarcodeFormats::LINEAR_CODES.bits(),
+        ),
+        (
+            "MatrixCodes".to_owned(),
+            reader_core::BarcodeFormats::MATRIX_CODES.bits(),
+        ),
+        (
+            "RetailCodes".to_owned(),
+            reader_core::BarcodeFormats::RETAIL_CODES.bits(),
+        ),
+        ("Any".to_owned(), reader_core::BarcodeFormats::ANY.bits()),
+    ]);
+    let flag = py
+        .import("enum")?
+        .getattr("IntFlag")?
+        .call1(("BarcodeFormats", members))?;
+    flag.setattr("__module__", "pyrxing")?;
+    Ok(flag)
+}
+
+struct _Binarizer<'a>(&'a str);
+
+impl TryFrom<_Binarizer<'_>> for reader_core::Binarizer {
+    type Error = String;
+    fn try_from(value: _Binarizer<'_>) -> std::result::Result<Self, Self::Error> {
+        use reader_core::Binarizer as B;
+        let v = match value.0 {
+            "LocalAverage" => B::LocalAverage,
+            "GlobalHistogram" => B::GlobalHistogram,
+            "FixedThreshold" => B::FixedThreshold,
+            "BoolCast" => B::BoolCast,
             other => {
-                return Err(format!("`{}` is not supported", other));
+                return Err(format!("`{}` is not a supported binarizer", other));
             }
         };
         Ok(v)
     }
 }
 
-fn decode(obj: &Bound<'_, PyAny>, formats: Option<Vec<String>>, multi: bool) -> Result<Decoded> {
-    let image_source = &get_image_source(obj)?;
-    let gray_image = (image_source).try_into()?;
+struct _TextMode<'a>(&'a str);
 
-    let formats = formats
-        .unwrap_or_else(|| vec![])
-        .into_iter()
-        .filter_map(|bf| TryInto::<reader_core::BarcodeFormat>::try_into(_BarcodeFormat(bf)).ok())
-        .collect::<Vec<_>>();
+impl TryFrom<_TextMode<'_>> for reader_core::TextMode {
+    type Error = String;
+    fn try_from(value: _TextMode<'_>) -> std::result::Result<Self, Self::Error> {
+        use reader_core::TextMode as TM;
+        let v = match value.0 {
+            "Plain" => TM::Plain,
+            "ECI" => TM::ECI,
+            "HRI" => TM::HRI,
+            "Hex" => TM::Hex,
+            "Escaped" => TM::Escaped,
+            other => {
+                return Err(format!("`{}` is not a supported text mode", other));
+            }
+        };
+        Ok(v)
+    }
+}
+
+struct _EanAddOnSymbol<'a>(&'a str);
+
+impl TryFrom<_EanAddOnSymbol<'_>> for reader_core::EanAddOnSymbol {
+    type Error = String;
+    fn try_from(value: _EanAddOnSymbol<'_>) -> std::result::Result<Self, Self::Error> {
+        use reader_core::EanAddOnSymbol as EAS;
+        let v = match value.0 {
+            "Ignore" => EAS::Ignore,
+            "Read" => EAS::Read,
+            "Require" => EAS::Require,
+            other => {
+                return Err(format!("`{}` is not a supported add-on mode", other));
+            }
+        };
+        Ok(v)
+    }
+}
+
+/// `channel` argument: either the name of a channel or `(r, g, b)` weights.
+#[derive(FromPyObject)]
+enum _Channel {
+    Name(String),
+    Weights((f32, f32, f32)),
+}
+
+impl TryFrom<_Channel> for reader_core::Channel {
+    type Error = String;
+    fn try_from(value: _Channel) -> std::result::Result<Self, Self::Error> {
+        use reader_core::Channel as C;
+        let v = match value {
+            _Channel::Name(name) => match name.as_str() {
+                "Rec601" => C::Rec601,
+                "Rec709" => C::Rec709,
+                "Red" => C::Red,
+                "Green" => C::Green,
+                "Blue" => C::Blue,
+                "Max" => C::Max,
+                "Min" => C::Min,
+                other => {
+                    return Err(format!("`{}` is not a supported channel", other));
+                }
+            },
+            _Channel::Weights((r, g, b)) => {
+                if ![r, g, b].iter().all(|w| w.is_finite()) {
+                    return Err("channel weights must be finite".to_string());
+                }
+                C::Weights([r, g, b])
+            }
+        };
+        Ok(v)
+    }
+}
+
+fn decode(
+    obj: &Bound<'_, PyAny>,
+    reader: &reader_core::Reader,
+    image_options: &ImageOptions,
+    multi: bool,
+) -> Result<Decoded> {
+    let image_source = get_image_source(obj)?;
+    let image_data = image_source.data(image_options);
+
+    // Everything below works on data copied or pinned above, so other Python
+    // threads may run while the file is decoded and the barcodes are read.
+    obj.py()
+        .detach(|| decode_image_data(image_data, reader, multi))
+}
+
+/// Queues decoding `obj` on the shared worker threads and returns an
+/// `asyncio.Future` of the running event loop, resolved through
+/// `call_soon_threadsafe` once done.
+///
+/// The image is taken from `obj` before returning, i.e. file-like objects are
+/// read and PIL images copied, so the caller is free to close or change them
+/// and the worker never touches them. Only opening paths and decoding are
+/// left to the worker.
+///
+/// Cancelling the future only detaches it: the decode runs to completion and
+/// its result is dropped.
+fn decode_async<'py>(
+    obj: &Bound<'py, PyAny>,
+    reader: reader_core::Reader,
+    image_options: ImageOptions,
+    multi: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = obj.py();
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+
+    let image_source = get_image_source(obj)?.unbind();
+    let (event_loop_ref, future_ref) = (event_loop.unbind(), future.clone().unbind());
+    reader_core::spawn(move || {
+        Python::attach(|py| {
+            let value = panic::catch_unwind(AssertUnwindSafe(|| {
+                let image_source = image_source.bind(py);
+                let image_data = image_source.data(&image_options);
+                let decoded = py.detach(|| decode_image_data(image_data, &reader, multi))?;
+                match decoded {
+                    Decoded::Single(result) => result.map(|r| *r).into_pyobject(py),
+                    Decoded::Multi(results) => results.into_pyobject(py),
+                }
+                .map(Bound::unbind)
+            }))
+            .unwrap_or_else(|_| Err(PanicException::new_err("decoding the image panicked")));
+            let (value, failed) = match value {
+                Ok(value) => (value, false),
+                Err(e) => (e.into_value(py).into_any(), true),
+            };
+
+            // Fails only when the loop has been closed meanwhile, in which case
+            // nobody is left to await the result.
+            let _ = wrap_pyfunction!(resolve_future, py).and_then(|callback| {
+                event_loop_ref.call_method1(
+                    py,
+                    "call_soon_threadsafe",
+                    (callback, future_ref, value, failed),
+                )
+            });
+        })
+    });
+
+    Ok(future)
+}
+
+/// Runs on the event loop; the future may have been cancelled in the meantime.
+#[pyfunction]
+fn resolve_future(
+    future: &Bound<'_, PyAny>,
+    value: &Bound<'_, PyAny>,
+    failed: bool,
+) -> PyResult<()> {
+    if future.call_method0("cancelled")?.is_truthy()? {
+        return Ok(());
+    }
+    if failed {
+        future.call_method1("set_exception", (value,))?;
+    } else {
+        future.call_method1("set_result", (value,))?;
+    }
+    Ok(())
+}
+
+fn decode_image_data(
+    image_data: ImageData<'_>,
+    reader: &reader_core::Reader,
+    multi: bool,
+) -> Result<Decoded> {
+    let image: Image = image_data.try_into()?;
 
     if multi {
-        reader_core::decode_multiple(gray_image, formats.as_slice())
-            .map_err(|e| error::Error::Decode(e.to_string()))
+        reader
+            .read_all(image)
+            .map_err(error::Error::from)
             .and_then(|result| {
                 result
                     .into_iter()
@@ -263,50 +1126,303 @@ fn decode(obj: &Bound<'_, PyAny>, formats: Option<Vec<String>>, multi: bool) ->
                     .map(Decoded::Multi)
             })
     } else {
-        reader_core::decode_single(gray_image, formats.as_slice())
-            .map_err(|e| error::Error::Decode(e.to_string()))
+        reader
+            .read(image)
+            .map_err(error::Error::from)
             .and_then(|opt| {
-                opt.map(|r| r.try_into())
+                opt.map(|r| r.try_into().map(Box::new))
                     .transpose()
                     .map(Decoded::Single)
             })
     }
 }
 
+/// An item of a batch, turned into an image on the worker thread that reads
+/// it, so that files are read and PIL images copied there, one at a time.
+struct BatchImage {
+    obj: PyResult<Py<PyAny>>,
+    options: ImageOptions,
+}
+
+impl reader_core::IntoImage for BatchImage {
+    type Error = error::Error;
+
+    fn read_with<R: Send>(self, read: impl FnOnce(Image<'_>) -> R + Send) -> Result<R> {
+        Python::attach(|py| {
+            let obj = self.obj?.into_bound(py);
+            let image_source = get_image_source(&obj)?;
+            let image_data = image_source.data(&self.options);
+            py.detach(|| Ok(read(image_data.try_into()?)))
+        })
+    }
+}
+
+/// The items of a Python iterable, taken as worker threads ask for them.
+struct BatchImages {
+    iter: Py<PyIterator>,
+    options: ImageOptions,
+}
+
+impl Iterator for BatchImages {
+    type Item = BatchImage;
+
+    fn next(&mut self) -> Option<BatchImage> {
+        Python::attach(|py| {
+            let obj = self.iter.bind(py).clone().next()?;
+            Some(BatchImage {
+                obj: obj.map(Bound::unbind),
+                options: self.options,
+            })
+        })
+    }
+}
+
+/// Reads every image of the iterable `images` on up to `workers` threads of
+/// the shared pool with the GIL released. Each entry of the returned list is
+/// either the list of results for that image or the exception raised while
+/// reading it.
+fn decode_batch(
+    images: &Bound<'_, PyAny>,
+    reader: &reader_core::Reader,
+    image_options: &ImageOptions,
+    workers: usize,
+) -> PyResult<Vec<Py<PyAny>>> {
+    let py = images.py();
+    if images.is_instance_of::<PyString>() {
+        return Err(PyTypeError::new_err(
+            "images must be an iterable of images, not a single path",
+        ));
+    }
+    let images = BatchImages {
+        iter: images.try_iter()?.unbind(),
+        options: *image_options,
+    };
+
+    let results = py.detach(|| reader.read_batch(images, workers));
+    results
+        .into_iter()
+        .map(|result| {
+            let result = result.and_then(|results| {
+                results
+                    .into_iter()
+                    .map(DecodeResult::try_from)
+                    .collect::<Result<Vec<_>>>()
+            });
+            match result {
+                Ok(results) => Ok(results.into_pyobject(py)?.into_any().unbind()),
+                Err(e) => Ok(PyErr::from(e).into_value(py).into_any()),
+            }
+        })
+        .collect()
+}
+
+fn reader(
+    formats: Option<_BarcodeFormats>,
+    options: DecodeOptions,
+) -> PyResult<reader_core::Reader> {
+    let formats = formats
+        .map(reader_core::BarcodeFormats::try_from)
+        .transpose()
+        .map_err(pyo3::exceptions::PyValueError::new_err)?
+        .unwrap_or(reader_core::BarcodeFormats::ANY);
+    Ok(reader_core::Reader::new(formats, options))
+}
+
+/// The keyword options of `BarcodeReader`, which every read function takes
+/// as well and passes on to it.
+fn options(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<(DecodeOptions, ImageOptions)> {
+    let mut options = DecodeOptions::new();
+    let mut image_options = ImageOptions::default();
+    for (key, value) in kwargs.into_iter().flatten() {
+        let key = key.extract::<PyBackedStr>()?;
+        match &*key {
+            "binarizer" => {
+                let binarizer = _Binarizer(&value.extract::<PyBackedStr>()?).try_into();
+                options = options.with_binarizer(binarizer.map_err(PyValueError::new_err)?);
+            }
+            "text_mode" => {
+                let text_mode = _TextMode(&value.extract::<PyBackedStr>()?).try_into();
+                options = options.with_text_mode(text_mode.map_err(PyValueError::new_err)?);
+            }
+            "ean_add_on" => {
+                let ean_add_on = _EanAddOnSymbol(&value.extract::<PyBackedStr>()?).try_into();
+                options =
+                    options.with_ean_add_on_symbol(ean_add_on.map_err(PyValueError::new_err)?);
+            }
+            "pure" => options = options.with_pure(value.extract()?),
+            "return_errors" => options = options.with_return_errors(value.extract()?),
+            "try_harder" => options = options.with_try_harder(value.extract()?),
+            "try_rotate" => options = options.with_try_rotate(value.extract()?),
+            "try_invert" => options = options.with_try_invert(value.extract()?),
+            "try_downscale" => options = options.with_try_downscale(value.extract()?),
+            "background" => image_options.background = value.extract()?,
+            "window" => {
+                let window = value.extract::<Option<(f64, f64)>>()?;
+                if let Some((low, high)) = window
+                    && (low.is_nan() || high.is_nan() || low >= high)
+                {
+                    return Err(PyValueError::new_err(
+                        "`window` must be a (low, high) pair with low < high",
+                    ));
+                }
+                image_options.window = window;
+            }
+            "channel" => {
+                let channel = value
+                    .extract::<Option<_Channel>>()?
+                    .map(Channel::try_from)
+                    .transpose()
+                    .map_err(PyValueError::new_err)?
+                    .unwrap_or_default();
+                // Images read in place are converted by the reader, all
+                // others while they are turned into gray levels.
+                options = options.with_channel(channel);
+                image_options.channel = channel;
+            }
+            "color_order" => {
+                image_options.bgr = match &*value.extract::<PyBackedStr>()? {
+                    "RGB" => false,
+                    "BGR" => true,
+                    other => {
+                        return Err(PyValueError::new_err(format!(
+                            "`{}` is not a supported color order, expected RGB or BGR",
+                            other
+                        )));
+                    }
+                };
+            }
+            other => {
+                return Err(PyTypeError::new_err(format!(
+                    "`{}` is not a supported option",
+                    other
+                )));
+            }
+        }
+    }
+    Ok((options, image_options))
+}
+
 #[pyfunction]
-#[pyo3(signature = (image, formats = None))]
+#[pyo3(signature = (image, formats = None, **options))]
 fn read_barcode(
     image: &Bound<'_, PyAny>,
-    formats: Option<Vec<String>>,
+    formats: Option<_BarcodeFormats>,
+    options: Option<&Bound<'_, PyDict>>,
 ) -> PyResult<Option<DecodeResult>> {
-    decode(image, formats, false)
-        .map(|decoded| match decoded {
-            Decoded::Single(r) => r,
-            _ => unreachable!(),
-        })
-        .map_err(PyErr::from)
+    BarcodeReader::new(formats, options)?.read(image)
 }
 
 #[pyfunction]
-#[pyo3(signature = (image, formats = None))]
+#[pyo3(signature = (image, formats = None, **options))]
 fn read_barcodes(
     image: &Bound<'_, PyAny>,
-    formats: Option<Vec<String>>,
+    formats: Option<_BarcodeFormats>,
+    options: Option<&Bound<'_, PyDict>>,
 ) -> PyResult<Vec<DecodeResult>> {
-    decode(image, formats, true)
-        .map(|decoded| match decoded {
-            Decoded::Multi(results) => results,
-            _ => unreachable!(),
+    BarcodeReader::new(formats, options)?.read_all(image)
+}
+
+/// Reader configured once with formats and options, for decoding many images.
+#[pyclass(module = "pyrxing", frozen)]
+struct BarcodeReader {
+    inner: reader_core::Reader,
+    image_options: ImageOptions,
+}
+
+#[pymethods]
+impl BarcodeReader {
+    #[new]
+    #[pyo3(signature = (formats = None, **options))]
+    fn new(
+        formats: Option<_BarcodeFormats>,
+        options: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Self> {
+        let (options, image_options) = self::options(options)?;
+        Ok(Self {
+            inner: reader(formats, options)?,
+            image_options,
         })
-        .map_err(PyErr::from)
+    }
+
+    fn read(&self, image: &Bound<'_, PyAny>) -> PyResult<Option<DecodeResult>> {
+        decode(image, &self.inner, &self.image_options, false)
+            .map(|decoded| match decoded {
+                Decoded::Single(r) => r.map(|r| *r),
+                _ => unreachable!(),
+            })
+            .map_err(PyErr::from)
+    }
+
+    fn read_all(&self, image: &Bound<'_, PyAny>) -> PyResult<Vec<DecodeResult>> {
+        decode(image, &self.inner, &self.image_options, true)
+            .map(|decoded| match decoded {
+                Decoded::Multi(results) => results,
+                _ => unreachable!(),
+            })
+            .map_err(PyErr::from)
+    }
+
+    #[pyo3(signature = (images, *, workers = 0))]
+    fn read_batch(&self, images: &Bound<'_, PyAny>, workers: usize) -> PyResult<Vec<Py<PyAny>>> {
+        decode_batch(images, &self.inner, &self.image_options, workers)
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (images, formats = None, *, workers = 0, **options))]
+fn read_barcodes_batch(
+    images: &Bound<'_, PyAny>,
+    formats: Option<_BarcodeFormats>,
+    workers: usize,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Vec<Py<PyAny>>> {
+    BarcodeReader::new(formats, options)?.read_batch(images, workers)
+}
+
+#[pyfunction]
+#[pyo3(signature = (image, formats = None, **options))]
+fn read_barcode_async<'py>(
+    image: &Bound<'py, PyAny>,
+    formats: Option<_BarcodeFormats>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let reader = BarcodeReader::new(formats, options)?;
+    decode_async(image, reader.inner, reader.image_options, false)
+}
+
+#[pyfunction]
+#[pyo3(signature = (image, formats = None, **options))]
+fn read_barcodes_async<'py>(
+    image: &Bound<'py, PyAny>,
+    formats: Option<_BarcodeFormats>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let reader = BarcodeReader::new(formats, options)?;
+    decode_async(image, reader.inner, reader.image_options, true)
+}
+
+#[pyfunction]
+fn reassemble(results: Vec<PyRef<'_, DecodeResult>>) -> Vec<StructuredMessage> {
+    reader_core::reassemble(results.iter().filter_map(|r| r.segment.clone()))
+        .into_iter()
+        .map(StructuredMessage::from)
+        .collect()
 }
 
-#[pymodule]
+#[pymodule(gil_used = false)]
 fn pyrxing(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<error::PyBarcodeDecodeError>()?;
     m.add_class::<error::PyImageError>()?;
     m.add_class::<DecodeResult>()?;
+    m.add_class::<StructuredMessage>()?;
+    m.add_class::<BitMatrix>()?;
+    m.add_class::<BarcodeReader>()?;
+    m.add("BarcodeFormats", barcode_formats_flag(m.py())?)?;
     m.add_function(wrap_pyfunction!(read_barcode, m)?)?;
     m.add_function(wrap_pyfunction!(read_barcodes, m)?)?;
+    m.add_function(wrap_pyfunction!(read_barcodes_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(read_barcode_async, m)?)?;
+    m.add_function(wrap_pyfunction!(read_barcodes_async, m)?)?;
+    m.add_function(wrap_pyfunction!(reassemble, m)?)?;
     Ok(())
 }
@@ -1,3 +1,14 @@
+import asyncio
+import io
+import pathlib
+import struct
+import sys
+import sysconfig
+import threading
+import time
+import zlib
+from concurrent.futures import ThreadPoolExecutor
+
 import pytest
 from PIL import Image
 
@@ -66,3 +77,821 @@ def test_read():
         assert len(res) == 1
         assert res[0].text == v['value']
         assert res[0].format == v['format']
+
+
+def test_read_with_options():
+    i = Image.open("assets/test_qr_code.png")
+    res = pyrxing.read_barcode(
+        i, try_harder=False, try_rotate=False, try_invert=False, try_downscale=False
+    )
+    assert res is not None
+    assert res.text == "https://demo.net/demo/7809"
+
+    res = pyrxing.read_barcodes(i, try_rotate=False, try_invert=False)
+    assert len(res) == 1
+    assert res[0].text == "https://demo.net/demo/7809"
+
+
+def test_read_with_binarizer():
+    i = Image.open("assets/test_qr_code.png")
+    for binarizer in ["LocalAverage", "GlobalHistogram", "FixedThreshold", "BoolCast"]:
+        res = pyrxing.read_barcode(i, binarizer=binarizer)
+        assert res is not None
+        assert res.text == "https://demo.net/demo/7809"
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(i, binarizer="Unknown")
+
+
+def test_read_pure():
+    res = pyrxing.read_barcode(Image.open("assets/test_qr_code.png"), pure=True)
+    assert res is not None
+    assert res.text == "https://demo.net/demo/7809"
+
+    with pytest.raises(pyrxing.BarcodeDecodeError):
+        pyrxing.read_barcode(Image.open("assets/test_ean13.png"), formats=["QRCode"], pure=True)
+
+    # every entry point takes the same options
+    res = pyrxing.read_barcodes(Image.open("assets/test_qr_code.png"), pure=True)
+    assert [r.text for r in res] == ["https://demo.net/demo/7809"]
+    res = pyrxing.read_barcodes_batch(["assets/test_qr_code.png"], pure=True)
+    assert [r.text for r in res[0]] == ["https://demo.net/demo/7809"]
+
+
+def test_read_with_text_mode():
+    i = Image.open("assets/test_data_bar.png")
+    assert pyrxing.read_barcode(i).text == "(01)01234567890128"
+    assert pyrxing.read_barcode(i, text_mode="HRI").text == "(01)01234567890128"
+    assert pyrxing.read_barcode(i, text_mode="Plain").text == "0101234567890128"
+    assert pyrxing.read_barcode(i, text_mode="Hex").text == " ".join(
+        f"{b:02X}" for b in b"0101234567890128"
+    )
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(i, text_mode="Unknown")
+
+
+def test_read_ean_add_on():
+    i = Image.open("assets/test_ean13_add_on.png")
+    res = pyrxing.read_barcode(i)
+    assert res is not None
+    assert res.text == "9780201379624"
+    assert res.main_text == "9780201379624"
+    assert res.add_on is None
+
+    res = pyrxing.read_barcode(i, ean_add_on="Require")
+    assert res is not None
+    assert res.text == "978020137962451299"
+    assert res.main_text == "9780201379624"
+    assert res.add_on == "51299"
+
+    assert pyrxing.read_barcode(Image.open("assets/test_ean13.png"), ean_add_on="Require") is None
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(i, ean_add_on="Unknown")
+
+
+def test_read_return_errors():
+    i = Image.open("assets/test_qr_code_damaged.png")
+    assert pyrxing.read_barcode(i, formats=["QRCode"]) is None
+
+    res = pyrxing.read_barcode(i, formats=["QRCode"], return_errors=True)
+    assert res is not None
+    assert res.format == "QRCode"
+    assert not res.is_valid
+    assert res.error_kind == "Checksum"
+    assert res.error_message
+
+    res = pyrxing.read_barcodes(Image.open("assets/test_qr_code.png"), return_errors=True)
+    assert len(res) == 1
+    assert res[0].is_valid
+    assert res[0].error_kind is None
+    assert res[0].error_message is None
+
+
+def test_read_bytes():
+    res = pyrxing.read_barcode(Image.open("assets/test_qr_code.png"))
+    assert res is not None
+    assert res.bytes == b"https://demo.net/demo/7809"
+    assert res.bytes_eci.startswith(b"]Q")
+    assert res.bytes_eci.endswith(b"https://demo.net/demo/7809")
+
+
+def test_read_points():
+    res = pyrxing.read_barcode(Image.open("assets/test_qr_code_skewed.png"))
+    assert res is not None
+    assert res.text == "https://demo.net/demo/7809"
+    top_left, top_right, bottom_right, bottom_left = res.points
+    assert bottom_left.x - top_left.x > 20
+    assert bottom_right.x - top_right.x > 20
+    assert abs(top_left.y - top_right.y) <= 2
+    assert abs(bottom_left.y - bottom_right.y) <= 2
+
+
+def test_read_orientation_and_flags():
+    res = pyrxing.read_barcode(Image.open("assets/test_qr_code.png"))
+    assert res is not None
+    assert res.orientation == 0
+    assert not res.is_mirrored
+    assert not res.is_inverted
+
+    res = pyrxing.read_barcode(Image.open("assets/test_qr_code_rotated_inverted.png"))
+    assert res is not None
+    assert res.text == "https://demo.net/demo/7809"
+    assert res.orientation == 90
+    assert not res.is_mirrored
+    assert res.is_inverted
+
+
+def test_read_symbology_and_content_type():
+    expected = {
+        "assets/test_qr_code.png": ("]Q1", "Text"),
+        "assets/test_code128.png": ("]C0", "Text"),
+        "assets/test_ean13.png": ("]E0", "Text"),
+        "assets/test_data_bar.png": ("]e0", "GS1"),
+    }
+    for path, (symbology_identifier, content_type) in expected.items():
+        res = pyrxing.read_barcode(Image.open(path))
+        assert res is not None
+        assert res.symbology_identifier == symbology_identifier
+        assert res.content_type == content_type
+
+
+def test_read_metadata():
+    expected = {
+        "assets/test_qr_code.png": ("M", "2"),
+        "assets/test_micro_qr.png": ("L", "M2"),
+        "assets/test_data_matrix.png": (None, "12x26"),
+        "assets/test_pdf417.png": ("50%", None),
+        "assets/test_code128.png": (None, None),
+    }
+    for path, (ec_level, version) in expected.items():
+        res = pyrxing.read_barcode(Image.open(path))
+        assert res is not None
+        assert res.ec_level == ec_level
+        assert res.version == version
+        assert not res.reader_init
+
+
+def test_reassemble():
+    res = pyrxing.read_barcode(Image.open("assets/test_qr_code.png"))
+    assert res is not None
+    assert res.sequence_size is None
+    assert res.sequence_index is None
+    assert res.sequence_id is None
+    assert pyrxing.reassemble([res]) == []
+
+    first = pyrxing.read_barcodes(Image.open("assets/test_qr_code_sequence_a.png"))
+    assert sorted(r.sequence_index for r in first) == [0, 2]
+    assert all(r.sequence_size == 3 and r.sequence_id == "77" for r in first)
+
+    messages = pyrxing.reassemble(first)
+    assert len(messages) == 1
+    assert messages[0].format == "QRCode"
+    assert messages[0].id == "77"
+    assert messages[0].size == 3
+    assert messages[0].missing == [1]
+    assert not messages[0].is_complete
+
+    second = pyrxing.read_barcodes(Image.open("assets/test_qr_code_sequence_b.png"))
+    messages = pyrxing.reassemble(first + second)
+    assert len(messages) == 1
+    assert messages[0].missing == []
+    assert messages[0].is_complete
+    assert messages[0].text == (
+        "Shipment 4711 part 1 of 3;"
+        "Shipment 4711 part 2 of 3;"
+        "Shipment 4711 part 3 of 3;"
+    )
+    assert messages[0].bytes == messages[0].text.encode()
+
+
+def test_read_symbol():
+    res = pyrxing.read_barcode(Image.open("assets/test_qr_code.png"))
+    assert res is not None
+    symbol = res.symbol
+    assert symbol is not None
+    assert (symbol.width, symbol.height) == (25, 25)
+    assert symbol.row_stride == 4
+    assert len(symbol.bytes) == 4 * 25
+
+    rows = symbol.to_list()
+    assert len(rows) == 25
+    assert all(len(row) == 25 for row in rows)
+    # finder pattern with separator in the top-left and top-right corners
+    assert rows[0][:8] == [True] * 7 + [False]
+    assert rows[0][-8:] == [False] + [True] * 7
+    assert rows[1][:7] == [True] + [False] * 5 + [True]
+    assert symbol.bytes[0] == 0b11111110
+
+    res = pyrxing.read_barcode(Image.open("assets/test_code128.png"))
+    assert res is not None
+    assert res.symbol is None
+
+
+def test_read_with_every_format_name():
+    for name in [
+        "AztecCode",
+        "AztecRune",
+        "CompactPDF417",
+        "EAN2",
+        "EAN5",
+        "EANUPC",
+        "ISBN",
+        "MicroPDF417",
+        "PZN",
+        "QRCodeModel1",
+        "QRCodeModel2",
+    ]:
+        pyrxing.read_barcodes(Image.open("assets/test_qr_code.png"), formats=[name])
+
+    res = pyrxing.read_barcode(Image.open("assets/test_ean13.png"), formats=["EANUPC"])
+    assert res is not None
+    assert res.format == "EAN13"
+
+
+def test_read_with_unknown_format():
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(Image.open("assets/test_qr_code.png"), formats=["Typo"])
+    with pytest.raises(ValueError):
+        pyrxing.read_barcodes(
+            Image.open("assets/test_qr_code.png"), formats=["QRCode", "Typo"]
+        )
+
+
+def test_read_with_barcode_formats_flag():
+    formats = pyrxing.BarcodeFormats
+    assert formats.DataBarOmni in formats.DataBar
+    assert formats.DataBar in formats.RetailCodes
+    assert formats.RetailCodes in formats.LinearCodes
+    assert formats.LinearCodes | formats.MatrixCodes == formats.Any
+
+    res = pyrxing.read_barcode(
+        Image.open("assets/test_data_bar.png"), formats=formats.RetailCodes
+    )
+    assert res is not None
+    assert res.format == "DataBarOmni"
+
+    no_data_bar = formats.Any & ~formats.DataBar
+    res = pyrxing.read_barcode(
+        Image.open("assets/test_data_bar.png"), formats=no_data_bar
+    )
+    assert res is None
+    res = pyrxing.read_barcode(
+        Image.open("assets/test_qr_code.png"), formats=no_data_bar
+    )
+    assert res is not None
+    assert res.format == "QRCode"
+
+    res = pyrxing.read_barcode(
+        Image.open("assets/test_micro_qr.png"),
+        formats=formats.QRCode & ~formats.MicroQRCode,
+    )
+    assert res is None
+
+    qr_code = Image.open("assets/test_qr_code.png")
+    assert pyrxing.read_barcode(qr_code, formats=formats.QRCode & formats.EAN13) is None
+    none_left = (formats.QRCode | formats.EAN13) - formats.QRCode - formats.EAN13
+    assert pyrxing.read_barcodes(qr_code, formats=none_left) == []
+    assert pyrxing.read_barcode(qr_code, formats=0) is None
+    assert pyrxing.read_barcode(qr_code, formats=[]) is not None
+    assert pyrxing.read_barcode(qr_code) is not None
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(Image.open("assets/test_qr_code.png"), formats=1 << 62)
+
+
+def test_barcode_reader():
+    reader = pyrxing.BarcodeReader(formats=["QRCode", "EAN13"])
+    res = reader.read(Image.open("assets/test_qr_code.png"))
+    assert res is not None
+    assert res.format == "QRCode"
+    res = reader.read("assets/test_ean13.png")
+    assert res is not None
+    assert res.text == "2708639496369"
+    assert reader.read(Image.open("assets/test_code128.png")) is None
+
+    reader = pyrxing.BarcodeReader(pyrxing.BarcodeFormats.QRCode)
+    results = reader.read_all(Image.open("assets/test_qr_code_sequence_a.png"))
+    assert len(results) == 2
+
+    reader = pyrxing.BarcodeReader(pure=True)
+    with pytest.raises(pyrxing.BarcodeDecodeError):
+        reader.read(Image.open("assets/test_qr_code_skewed.png"))
+
+    with pytest.raises(ValueError):
+        pyrxing.BarcodeReader(["Typo"])
+    with pytest.raises(ValueError):
+        pyrxing.BarcodeReader(binarizer="Typo")
+    with pytest.raises(TypeError):
+        pyrxing.BarcodeReader(try_hard=True)
+    with pytest.raises(TypeError):
+        pyrxing.read_barcode("assets/test_qr_code.png", try_hard=True)
+
+
+def test_read_from_threads():
+    paths = list(ASSETS) * 4
+    images = [Image.open(path) for path in paths]
+    with ThreadPoolExecutor(max_workers=4) as executor:
+        from_paths = list(executor.map(pyrxing.read_barcode, paths))
+        from_images = list(executor.map(pyrxing.read_barcode, images))
+
+    for path, a, b in zip(paths, from_paths, from_images):
+        assert a is not None and b is not None
+        assert a.text == b.text == ASSETS[path]["value"]
+
+
+def test_read_barcodes_batch():
+    paths = list(ASSETS)
+    images = [Image.open(path) for path in paths]
+    results = pyrxing.read_barcodes_batch(
+        [*paths, "assets/missing.png", 42, *images], workers=3
+    )
+
+    assert len(results) == 2 * len(paths) + 2
+    decoded = results[: len(paths)] + results[-len(paths) :]
+    for path, result in zip(paths * 2, decoded):
+        assert not isinstance(result, Exception)
+        assert result[0].text == ASSETS[path]["value"]
+    assert isinstance(results[len(paths)], FileNotFoundError)
+    assert isinstance(results[len(paths) + 1], ValueError)
+
+    reader = pyrxing.BarcodeReader(formats=["QRCode"])
+    results = reader.read_batch(["assets/test_qr_code.png", "assets/test_ean13.png"])
+    assert [len(result) for result in results] == [1, 0]
+    assert pyrxing.read_barcodes_batch([]) == []
+
+    class Empty:
+        mode = "L"
+        width, height = 0, 0
+
+        def tobytes(self):
+            return b""
+
+        def convert(self, mode):
+            return self
+
+        def load(self):
+            pass
+
+    results = pyrxing.read_barcodes_batch(
+        (image for image in [Empty(), "assets/test_qr_code.png"]), workers=2
+    )
+    assert isinstance(results[0], pyrxing.ImageError)
+    assert results[1][0].text == ASSETS["assets/test_qr_code.png"]["value"]
+    with pytest.raises(TypeError):
+        pyrxing.read_barcodes_batch("assets/test_qr_code.png")
+
+
+def test_read_async():
+    async def read_all():
+        return await asyncio.gather(
+            *(pyrxing.read_barcode_async(path) for path in ASSETS),
+            pyrxing.read_barcodes_async(Image.open("assets/test_qr_code.png")),
+            pyrxing.read_barcode_async("assets/test_ean13.png", formats=["QRCode"]),
+        )
+
+    *results, multi, none = asyncio.run(read_all())
+    for path, result in zip(ASSETS, results):
+        assert result.text == ASSETS[path]["value"]
+    assert [r.format for r in multi] == ["QRCode"]
+    assert none is None
+
+    async def read_missing():
+        await pyrxing.read_barcode_async("assets/missing.png")
+
+    with pytest.raises(FileNotFoundError):
+        asyncio.run(read_missing())
+    with pytest.raises(RuntimeError):
+        pyrxing.read_barcode_async("assets/test_qr_code.png")
+
+
+def test_read_async_cancelled():
+    async def cancel():
+        future = pyrxing.read_barcode_async("assets/test_qr_code.png")
+        future.cancel()
+        with pytest.raises(asyncio.CancelledError):
+            await future
+        # the decode still completes in the background without touching the future
+        return await pyrxing.read_barcode_async("assets/test_qr_code.png")
+
+    result = asyncio.run(cancel())
+    assert result.text == ASSETS["assets/test_qr_code.png"]["value"]
+
+
+def test_read_async_during_batch():
+    count = 200
+    taken = []
+
+    def images():
+        for _ in range(count):
+            taken.append(None)
+            time.sleep(0.005)
+            yield "assets/test_qr_code.png"
+
+    async def read():
+        return await pyrxing.read_barcode_async("assets/test_ean13.png")
+
+    with ThreadPoolExecutor(max_workers=1) as executor:
+        batch = executor.submit(pyrxing.read_barcodes_batch, images())
+        while not taken:
+            time.sleep(0.001)
+        # the async read runs between the images of the batch, not after it
+        result = asyncio.run(read())
+        assert len(taken) < count
+        assert len(batch.result()) == count
+    assert result.text == ASSETS["assets/test_ean13.png"]["value"]
+
+
+def test_read_async_on_workers():
+    expected = ASSETS["assets/test_qr_code.png"]["value"]
+    with open("assets/test_qr_code.png", "rb") as f:
+        data = f.read()
+    threads = []
+
+    class Recording(io.BytesIO):
+        def read(self, *args):
+            threads.append(threading.get_ident())
+            return super().read(*args)
+
+    class Failing(io.RawIOBase):
+        def read(self, *args):
+            raise OSError("unreadable")
+
+    async def read_all():
+        # the file is read before the future is returned, so it may be closed
+        with open("assets/test_qr_code.png", "rb") as f:
+            closed = pyrxing.read_barcode_async(f)
+        image = Image.open("assets/test_qr_code.png")
+        copied = pyrxing.read_barcode_async(image)
+        image.close()
+        with pytest.raises(OSError):
+            pyrxing.read_barcode_async(Failing())
+        # more reads than worker threads queue up instead of starting threads
+        return await asyncio.gather(
+            closed,
+            copied,
+            *(pyrxing.read_barcode_async(Recording(data)) for _ in range(32)),
+        )
+
+    results = asyncio.run(read_all())
+    assert [result.text for result in results] == [expected] * 34
+    # file-like objects are read by the caller, never on a worker thread
+    assert set(threads) == {threading.get_ident()}
+
+
+def test_read_from_threads_free_threaded():
+    if sysconfig.get_config_var("Py_GIL_DISABLED"):
+        # importing the module must not re-enable the GIL
+        assert not sys._is_gil_enabled()
+
+    reader = pyrxing.BarcodeReader()
+    shared = reader.read("assets/test_qr_code.png")
+    paths = list(ASSETS) * 8
+
+    def read(path):
+        # one reader and one result shared by all threads
+        assert shared.text == ASSETS["assets/test_qr_code.png"]["value"]
+        return reader.read(path).text
+
+    with ThreadPoolExecutor(max_workers=16) as executor:
+        texts = list(executor.map(read, paths))
+    assert texts == [ASSETS[path]["value"] for path in paths]
+
+
+def test_shared_result_from_threads_free_threaded():
+    shared = pyrxing.read_barcode("assets/test_qr_code.png")
+    assert shared is not None
+    expected = ASSETS["assets/test_qr_code.png"]["value"]
+
+    def read(_):
+        # every field of one result, read by all threads at the same time
+        symbol = shared.symbol
+        return (
+            shared.text,
+            shared.bytes,
+            shared.main_text,
+            shared.format,
+            shared.reader_init,
+            shared.is_valid,
+            shared.error_message,
+            shared.sequence_id,
+            len(shared.points),
+            symbol.width,
+            symbol.bytes,
+        )
+
+    with ThreadPoolExecutor(max_workers=16) as executor:
+        values = list(executor.map(read, range(256)))
+    assert values[0][:4] == (expected, expected.encode(), expected, "QRCode")
+    assert all(value == values[0] for value in values)
+
+
+class ArrayInterface:
+    def __init__(self, data, shape, strides=None, offset=0, typestr="|u1"):
+        self.__array_interface__ = {
+            "version": 3,
+            "shape": shape,
+            "typestr": typestr,
+            "data": data,
+            "strides": strides,
+            "offset": offset,
+        }
+
+
+def test_read_buffer():
+    expected = ASSETS["assets/test_qr_code.png"]["value"]
+    image = Image.open("assets/test_qr_code.png")
+    w, h = image.size
+
+    gray = image.convert("L").tobytes()
+    for array in [
+        memoryview(gray).cast("B", (h, w)),
+        memoryview(bytearray(gray)).cast("B", (h, w, 1)),
+        memoryview(image.convert("RGB").tobytes()).cast("B", (h, w, 3)),
+        memoryview(image.convert("RGBA").tobytes()).cast("B", (h, w, 4)),
+    ]:
+        result = pyrxing.read_barcode(array)
+        assert result is not None and result.text == expected
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(memoryview(gray).cast("B", (1, h, w, 1)))
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(memoryview(gray[: h * 4]).cast("B", (h, 2, 2)))
+
+
+def test_read_array_interface():
+    expected = ASSETS["assets/test_qr_code.png"]["value"]
+    image = Image.open("assets/test_qr_code.png").convert("L")
+    w, h = image.size
+    gray = image.tobytes()
+    rows = [gray[y * w : (y + 1) * w] for y in range(h)]
+
+    padded = b"".join(row + bytes(7) for row in rows)
+    doubled = bytes(b for b in gray for _ in range(2))
+    for array in [
+        ArrayInterface(gray, (h, w)),
+        # rows padded to a larger stride, as in a crop of a bigger frame
+        ArrayInterface(padded, (h, w), strides=(w + 7, 1)),
+        # every second byte, as in a single channel of an interleaved frame
+        ArrayInterface(doubled, (h, w), strides=(2 * w, 2)),
+    ]:
+        result = pyrxing.read_barcode(array)
+        assert result is not None and result.text == expected
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(ArrayInterface(gray, (h, w), typestr="<f4"))
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(ArrayInterface(gray[:-1], (h, w)))
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(
+            ArrayInterface(padded, (h, w), strides=(w + 7, 1), offset=8)
+        )
+    # negative strides and bare pointers cannot be checked against the data
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(
+            ArrayInterface(gray, (h, w), strides=(-w, 1), offset=(h - 1) * w)
+        )
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(ArrayInterface((id(gray), True), (h, w)))
+
+    # an L image whose array data is not `bytes` is read through `tobytes()`
+    class View:
+        mode = "L"
+        width, height = w, h
+
+        def __init__(self, data):
+            self.__array_interface__ = ArrayInterface(data, (h, w)).__array_interface__
+
+        def tobytes(self):
+            return gray
+
+        def convert(self, mode):
+            return self
+
+        def load(self):
+            pass
+
+    for data in [gray, bytearray(gray), memoryview(gray), (id(gray), True)]:
+        result = pyrxing.read_barcode(View(data))
+        assert result is not None and result.text == expected
+
+
+def test_read_encoded_image():
+    for path, expected in ASSETS.items():
+        with open(path, "rb") as f:
+            data = f.read()
+
+        for image in [
+            data,
+            bytearray(data),
+            memoryview(data),
+            io.BytesIO(data),
+            pathlib.Path(path),
+        ]:
+            result = pyrxing.read_barcode(image)
+            assert result is not None
+            assert result.text == expected["value"]
+
+        with open(path, "rb") as f:
+            result = pyrxing.read_barcode(f)
+            assert result is not None
+            assert result.text == expected["value"]
+
+    with pytest.raises(pyrxing.ImageError):
+        pyrxing.read_barcode(b"not an image")
+    with pytest.raises(ValueError):
+        with open("assets/test_qr_code.png", encoding="latin-1") as f:
+            pyrxing.read_barcode(f)
+    with pytest.raises(FileNotFoundError):
+        pyrxing.read_barcode(pathlib.Path("assets/missing.png"))
+
+
+def png(width, height, color_type, channels, data, depth=8):
+    def chunk(kind, body):
+        return (
+            struct.pack(">I", len(body))
+            + kind
+            + body
+            + struct.pack(">I", zlib.crc32(kind + body))
+        )
+
+    row = width * channels * depth // 8
+    raw = b"".join(
+        b"\x00" + data[y * row : (y + 1) * row] for y in range(height)
+    )
+    header = struct.pack(">IIBBBBB", width, height, depth, color_type, 0, 0, 0)
+    return (
+        b"\x89PNG\r\n\x1a\n"
+        + chunk(b"IHDR", header)
+        + chunk(b"IDAT", zlib.compress(raw))
+        + chunk(b"IEND", b"")
+    )
+
+
+def test_read_modes():
+    expected = ASSETS["assets/test_qr_code.png"]["value"]
+    image = Image.open("assets/test_qr_code.png").convert("L")
+    size = image.size
+    gray = image.tobytes()
+    dark = [v < 128 for v in gray]
+    # black everywhere, with the light modules made transparent
+    clear = bytes(255 if d else 0 for d in dark)
+
+    def pixels(values, channels):
+        return bytes(c for v in values for c in channels(v))
+
+    def pack(order, kind, values):
+        return struct.pack(f"{order}{len(values)}{kind}", *values)
+
+    packed = bytearray()
+    for y in range(size[1]):
+        row = [not d for d in dark[y * size[0] : (y + 1) * size[0]]]
+        for x in range(0, len(row), 8):
+            bits = row[x : x + 8]
+            packed.append(sum(0x80 >> i for i, bit in enumerate(bits) if bit))
+
+    palette = Image.frombytes("P", size, bytes(not d for d in dark))
+    palette.putpalette([0, 0, 0, 0, 0, 0])
+    palette.info["transparency"] = 1
+
+    images = [
+        Image.frombytes("1", size, bytes(packed)),
+        Image.frombytes("LA", size, pixels(clear, lambda a: (0, a))),
+        Image.frombytes("La", size, pixels(clear, lambda a: (0, a))),
+        Image.frombytes("RGBA", size, pixels(clear, lambda a: (0, 0, 0, a))),
+        Image.frombytes("RGBa", size, pixels(clear, lambda a: (0, 0, 0, a))),
+        Image.frombytes("RGBX", size, pixels(gray, lambda v: (v, v, v, 0))),
+        Image.frombytes("CMYK", size, pixels(gray, lambda v: (0, 0, 0, 255 - v))),
+        Image.frombytes("YCbCr", size, pixels(gray, lambda v: (v, 128, 128))),
+        Image.frombytes("LAB", size, pixels(gray, lambda v: (v, 128, 128))),
+        Image.frombytes("HSV", size, pixels(gray, lambda v: (0, 0, v))),
+        Image.frombytes("I;16", size, pack("<", "H", [v * 257 for v in gray])),
+        Image.frombytes("I;16B", size, pack(">", "H", [v * 257 for v in gray])),
+        Image.frombytes("I", size, pack("=", "i", [v * 100 - 9000 for v in gray])),
+        Image.frombytes("F", size, pack("=", "f", [v / 25500 for v in gray])),
+        palette,
+    ]
+    for image in images:
+        result = pyrxing.read_barcode(image)
+        assert result is not None, image.mode
+        assert result.text == expected
+
+    # transparent pixels are composited over the background
+    transparent = Image.frombytes("LA", size, pixels(clear, lambda a: (0, a)))
+    assert pyrxing.read_barcode(transparent, background=0) is None
+    transparent = png(*size, 6, 4, pixels(clear, lambda a: (0, 0, 0, a)))
+    assert pyrxing.read_barcode(transparent).text == expected
+    assert pyrxing.read_barcode(transparent, background=0) is None
+
+    # values outside the window are clamped to black or white
+    wide = Image.frombytes("I;16", size, pack("<", "H", [v + 1000 for v in gray]))
+    assert pyrxing.read_barcode(wide, window=(1000, 1255)).text == expected
+    assert pyrxing.read_barcode(wide, window=(2000, 3000)) is None
+
+    # image files too, by path or encoded
+    wide = png(*size, 0, 1, pack(">", "H", [v + 1000 for v in gray]), depth=16)
+    assert pyrxing.read_barcode(wide).text == expected
+    assert pyrxing.read_barcode(wide, window=(1000, 1255)).text == expected
+    assert pyrxing.read_barcode(wide, window=(2000, 3000)) is None
+    green = pack(">", "H", [c for v in gray for c in (0, v * 100, 0)])
+    wide = png(*size, 2, 3, green, depth=16)
+    assert pyrxing.read_barcode(wide).text == expected
+    assert pyrxing.read_barcode(wide, window=(0, 25500)).text == expected
+    assert pyrxing.read_barcode(wide, window=(30000, 40000)) is None
+
+    # light modules of a single value, dark ones not a number
+    single = [5.0 if v >= 128 else float("nan") for v in gray]
+    single = Image.frombytes("F", size, pack("=", "f", single))
+    assert pyrxing.read_barcode(single).text == expected
+    assert pyrxing.read_barcode(single, background=0) is None
+
+    # uint8 arrays ignore the window like other 8-bit images, but RGBA ones
+    # are composited
+    rgba = pixels(clear, lambda a: (0, 0, 0, a))
+    transparent = ArrayInterface(rgba, (size[1], size[0], 4))
+    assert pyrxing.read_barcode(transparent).text == expected
+    assert pyrxing.read_barcode(transparent, background=0) is None
+    assert pyrxing.read_barcode(transparent, window=(2000, 3000)).text == expected
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(wide, window=(1255, 1000))
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(wide, window=(float("nan"), 1255))
+
+    class Unsupported:
+        mode = "I;16S"
+        width, height = size
+
+        def tobytes(self):
+            return gray * 2
+
+        def convert(self, mode):
+            raise ValueError(mode)
+
+        def load(self):
+            pass
+
+    with pytest.raises(pyrxing.ImageError):
+        pyrxing.read_barcode(Unsupported())
+
+
+def test_read_channel():
+    expected = ASSETS["assets/test_qr_code.png"]["value"]
+    image = Image.open("assets/test_qr_code.png").convert("L")
+    w, h = image.size
+    # red ink on a green background of the same Rec. 601 luma
+    rgb = bytes(
+        c for v in image.tobytes() for c in ((255, 0, 0) if v < 128 else (0, 130, 0))
+    )
+    rgba = bytes(c for i in range(0, len(rgb), 3) for c in (*rgb[i : i + 3], 255))
+
+    def ycbcr(r, g, b):
+        y = 0.299 * r + 0.587 * g + 0.114 * b
+        cb = 128 - 0.168736 * r - 0.331264 * g + 0.5 * b
+        cr = 128 + 0.5 * r - 0.418688 * g - 0.081312 * b
+        return tuple(min(255, round(v)) for v in (y, cb, cr))
+
+    def pixels(ink, paper):
+        return bytes(c for v in image.tobytes() for c in (ink if v < 128 else paper))
+    for source in [
+        Image.frombytes("RGB", (w, h), rgb),
+        Image.frombytes("RGBA", (w, h), rgba),
+        # the same colors in other modes are converted to RGB first
+        Image.frombytes("YCbCr", (w, h), pixels(ycbcr(255, 0, 0), ycbcr(0, 130, 0))),
+        Image.frombytes("HSV", (w, h), pixels((0, 255, 255), (85, 255, 130))),
+        png(w, h, 2, 3, rgb),
+        png(w, h, 6, 4, rgba),
+        memoryview(rgb).cast("B", (h, w, 3)),
+        ArrayInterface(rgba, (h, w, 4)),
+    ]:
+        # Rec. 601 luma is the default for every kind of source
+        assert pyrxing.read_barcode(source) is None
+        assert pyrxing.read_barcode(source, channel="Rec601") is None
+        assert pyrxing.read_barcode(source, channel="Blue") is None
+        for channel in ["Rec709", "Red", "Green", "Max", (0.0, 1.0, 0.0)]:
+            result = pyrxing.read_barcode(source, channel=channel)
+            assert result is not None, channel
+            assert result.text == expected
+
+    reader = pyrxing.BarcodeReader(channel="Green")
+    assert reader.read(Image.frombytes("RGB", (w, h), rgb)).text == expected
+
+    # OpenCV frames keep their channels in BGR order
+    bgr = bytes(c for i in range(0, len(rgb), 3) for c in rgb[i : i + 3][::-1])
+    bgra = bytes(c for i in range(0, len(bgr), 3) for c in (*bgr[i : i + 3], 255))
+    for source in [
+        memoryview(bgr).cast("B", (h, w, 3)),
+        ArrayInterface(bgra, (h, w, 4)),
+    ]:
+        assert pyrxing.read_barcode(source, channel="Red") is None
+        result = pyrxing.read_barcode(source, channel="Red", color_order="BGR")
+        assert result is not None and result.text == expected
+        assert pyrxing.read_barcode(source, channel="Blue", color_order="BGR") is None
+
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(rgb, channel="Purple")
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(rgb, channel=(float("nan"), 1.0, 0.0))
+    with pytest.raises(ValueError):
+        pyrxing.read_barcode(rgb, color_order="GRB")
@@ -1,7 +1,21 @@
-use std::{borrow::Cow, cell::OnceCell, fmt::Display};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    fmt::Display,
+    ops::{BitAnd, BitOr, BitOrAssign, Not, Sub},
+    panic::{self, AssertUnwindSafe},
+    str::FromStr,
+    sync::{Arc, Mutex, OnceLock, PoisonError, mpsc},
+    thread,
+};
 
 use thiserror::Error;
-use zxingcpp::{Barcode, BarcodeFormat as ZxBarcodeFormat, ImageFormat, ImageView, PointI};
+use zxingcpp::{
+    Barcode, BarcodeError as ZxBarcodeError, BarcodeFormat as ZxBarcodeFormat,
+    BarcodeFormats as ZxBarcodeFormats, Binarizer as ZxBinarizer, ContentType as ZxContentType,
+    EanAddOnSymbol as ZxEanAddOnSymbol, ImageFormat as ZxImageFormat, ImageView, PointI,
+    TextMode as ZxTextMode,
+};
 
 #[derive(Error, Debug)]
 pub enum Error {
@@ -24,10 +38,6 @@ pub struct Point {
 }
 
 impl Point {
-    fn new(x: i32, y: i32) -> Self {
-        Self { x, y }
-    }
-
     pub fn x(&self) -> i32 {
         self.x
     }
@@ -86,6 +96,49 @@ pub enum BarcodeFormat {
     UPCE,
 }
 
+impl BarcodeFormat {
+    pub const ALL: [BarcodeFormat; 35] = {
+        use BarcodeFormat::*;
+        [
+            Aztec,
+            AztecCode,
+            AztecRune,
+            Codabar,
+            Code128,
+            Code39,
+            Code93,
+            CompactPDF417,
+            DXFilmEdge,
+            DataBar,
+            DataBarExp,
+            DataBarExpStk,
+            DataBarLtd,
+            DataBarOmni,
+            DataBarStk,
+            DataBarStkOmni,
+            DataMatrix,
+            EAN13,
+            EAN2,
+            EAN5,
+            EAN8,
+            EANUPC,
+            ISBN,
+            ITF,
+            MaxiCode,
+            MicroPDF417,
+            MicroQRCode,
+            PDF417,
+            PZN,
+            QRCode,
+            QRCodeModel1,
+            QRCodeModel2,
+            RMQRCode,
+            UPCA,
+            UPCE,
+        ]
+    };
+}
+
 impl Display for BarcodeFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use BarcodeFormat::*;
@@ -130,6 +183,17 @@ impl Display for BarcodeFormat {
     }
 }
 
+impl FromStr for BarcodeFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        BarcodeFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string() == s)
+            .ok_or_else(|| Error::UnsupportedFormat(s.to_owned()))
+    }
+}
+
 impl From<BarcodeFormat> for ZxBarcodeFormat {
     fn from(value: BarcodeFormat) -> Self {
         use BarcodeFormat::*;
@@ -219,10 +283,320 @@ impl TryFrom<ZxBarcodeFormat> for BarcodeFormat {
     }
 }
 
+impl BarcodeFormat {
+    /// The variants covered when the format is used as a filter, e.g. all
+    /// DataBar variants for `DataBar`.
+    const fn variants(self) -> &'static [BarcodeFormat] {
+        use BarcodeFormat::*;
+        match self {
+            Aztec => &[Aztec, AztecCode, AztecRune],
+            Code39 => &[Code39, PZN],
+            DataBar => &[
+                DataBar,
+                DataBarExp,
+                DataBarExpStk,
+                DataBarLtd,
+                DataBarOmni,
+                DataBarStk,
+                DataBarStkOmni,
+            ],
+            EANUPC => &[EANUPC, EAN13, EAN2, EAN5, EAN8, ISBN, UPCA, UPCE],
+            PDF417 => &[PDF417, CompactPDF417, MicroPDF417],
+            QRCode => &[QRCode, QRCodeModel1, QRCodeModel2, MicroQRCode, RMQRCode],
+            AztecCode => &[AztecCode],
+            AztecRune => &[AztecRune],
+            Codabar => &[Codabar],
+            Code128 => &[Code128],
+            Code93 => &[Code93],
+            CompactPDF417 => &[CompactPDF417],
+            DXFilmEdge => &[DXFilmEdge],
+            DataBarExp => &[DataBarExp],
+            DataBarExpStk => &[DataBarExpStk],
+            DataBarLtd => &[DataBarLtd],
+            DataBarOmni => &[DataBarOmni],
+            DataBarStk => &[DataBarStk],
+            DataBarStkOmni => &[DataBarStkOmni],
+            DataMatrix => &[DataMatrix],
+            EAN13 => &[EAN13],
+            EAN2 => &[EAN2],
+            EAN5 => &[EAN5],
+            EAN8 => &[EAN8],
+            ISBN => &[ISBN],
+            ITF => &[ITF],
+            MaxiCode => &[MaxiCode],
+            MicroPDF417 => &[MicroPDF417],
+            MicroQRCode => &[MicroQRCode],
+            PZN => &[PZN],
+            QRCodeModel1 => &[QRCodeModel1],
+            QRCodeModel2 => &[QRCodeModel2],
+            RMQRCode => &[RMQRCode],
+            UPCA => &[UPCA],
+            UPCE => &[UPCE],
+        }
+    }
+}
+
+/// A set of barcode formats. A symbology with several variants, such as
+/// `DataBar` or `EANUPC`, stands for all of them, so removing it removes every
+/// variant, and removing a single variant leaves the others enabled.
+///
+/// An empty set reads nothing. For compatibility with the former
+/// `&[BarcodeFormat]` arguments an empty slice converts to [`Self::ANY`].
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct BarcodeFormats(u64);
+
+impl BarcodeFormats {
+    pub const LINEAR_CODES: Self = Self::from_formats(&[
+        BarcodeFormat::Codabar,
+        BarcodeFormat::Code39,
+        BarcodeFormat::Code93,
+        BarcodeFormat::Code128,
+        BarcodeFormat::DataBar,
+        BarcodeFormat::DXFilmEdge,
+        BarcodeFormat::EANUPC,
+        BarcodeFormat::ITF,
+    ]);
+
+    pub const MATRIX_CODES: Self = Self::from_formats(&[
+        BarcodeFormat::Aztec,
+        BarcodeFormat::DataMatrix,
+        BarcodeFormat::MaxiCode,
+        BarcodeFormat::PDF417,
+        BarcodeFormat::QRCode,
+    ]);
+
+    pub const RETAIL_CODES: Self =
+        Self::from_formats(&[BarcodeFormat::DataBar, BarcodeFormat::EANUPC]);
+
+    pub const ANY: Self = Self::from_formats(&BarcodeFormat::ALL);
+
+    const fn from_formats(formats: &[BarcodeFormat]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < formats.len() {
+            let variants = formats[i].variants();
+            let mut j = 0;
+            while j < variants.len() {
+                bits |= 1 << variants[j] as u64;
+                j += 1;
+            }
+            i += 1;
+        }
+        Self(bits)
+    }
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns `None` if `bits` contains bits that do not correspond to a format.
+    pub const fn from_bits(bits: u64) -> Option<Self> {
+        if bits & !Self::ANY.0 == 0 {
+            Some(Self(bits))
+        } else {
+            None
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub const fn complement(self) -> Self {
+        Self::ANY.difference(self)
+    }
+
+    /// Formats in the set, in the order of [`BarcodeFormat::ALL`].
+    pub fn iter(&self) -> impl Iterator<Item = BarcodeFormat> + '_ {
+        BarcodeFormat::ALL
+            .into_iter()
+            .filter(|format| self.0 & (1 << *format as u64) != 0)
+    }
+
+    /// The formats to configure zxing with. Empty if the set is, or if
+    /// nothing but symbologies with some of their variants removed is left.
+    fn to_zx(self) -> Vec<ZxBarcodeFormat> {
+        // zxing treats a symbology as all of its variants, so only pass it on
+        // when none of them has been removed.
+        self.iter()
+            .filter(|format| self.contains((*format).into()))
+            .map(ZxBarcodeFormat::from)
+            .collect()
+    }
+}
+
+impl From<BarcodeFormat> for BarcodeFormats {
+    fn from(value: BarcodeFormat) -> Self {
+        Self::from_formats(&[value])
+    }
+}
+
+impl From<&[BarcodeFormat]> for BarcodeFormats {
+    /// An empty slice means no restriction, as it did before there were sets.
+    fn from(value: &[BarcodeFormat]) -> Self {
+        if value.is_empty() {
+            Self::ANY
+        } else {
+            Self::from_formats(value)
+        }
+    }
+}
+
+impl<const N: usize> From<&[BarcodeFormat; N]> for BarcodeFormats {
+    fn from(value: &[BarcodeFormat; N]) -> Self {
+        Self::from(value.as_slice())
+    }
+}
+
+impl FromIterator<BarcodeFormat> for BarcodeFormats {
+    fn from_iter<T: IntoIterator<Item = BarcodeFormat>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::empty(), |formats, format| formats | format)
+    }
+}
+
+impl<T: Into<BarcodeFormats>> BitOr<T> for BarcodeFormats {
+    type Output = Self;
+
+    fn bitor(self, rhs: T) -> Self {
+        self.union(rhs.into())
+    }
+}
+
+impl<T: Into<BarcodeFormats>> BitOrAssign<T> for BarcodeFormats {
+    fn bitor_assign(&mut self, rhs: T) {
+        *self = self.union(rhs.into());
+    }
+}
+
+impl<T: Into<BarcodeFormats>> BitAnd<T> for BarcodeFormats {
+    type Output = Self;
+
+    fn bitand(self, rhs: T) -> Self {
+        self.intersection(rhs.into())
+    }
+}
+
+impl<T: Into<BarcodeFormats>> Sub<T> for BarcodeFormats {
+    type Output = Self;
+
+    fn sub(self, rhs: T) -> Self {
+        self.difference(rhs.into())
+    }
+}
+
+impl Not for BarcodeFormats {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        self.complement()
+    }
+}
+
+impl<T: Into<BarcodeFormats>> BitOr<T> for BarcodeFormat {
+    type Output = BarcodeFormats;
+
+    fn bitor(self, rhs: T) -> BarcodeFormats {
+        BarcodeFormats::from(self).union(rhs.into())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ContentType {
+    Text,
+    Binary,
+    Mixed,
+    GS1,
+    ISO15434,
+    UnknownECI,
+}
+
+impl Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ContentType::*;
+        let v = match self {
+            Text => "Text",
+            Binary => "Binary",
+            Mixed => "Mixed",
+            GS1 => "GS1",
+            ISO15434 => "ISO15434",
+            UnknownECI => "UnknownECI",
+        };
+        write!(f, "{}", v)
+    }
+}
+
+impl From<ZxContentType> for ContentType {
+    fn from(value: ZxContentType) -> Self {
+        use ZxContentType::*;
+        match value {
+            Text => ContentType::Text,
+            Binary => ContentType::Binary,
+            Mixed => ContentType::Mixed,
+            GS1 => ContentType::GS1,
+            ISO15434 => ContentType::ISO15434,
+            UnknownECI => ContentType::UnknownECI,
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum BarcodeError {
+    #[error("Checksum error: {0}")]
+    Checksum(String),
+
+    #[error("Format error: {0}")]
+    Format(String),
+
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+}
+
+impl BarcodeError {
+    pub fn message(&self) -> &str {
+        match self {
+            BarcodeError::Checksum(message)
+            | BarcodeError::Format(message)
+            | BarcodeError::Unsupported(message) => message,
+        }
+    }
+
+    fn from_zx(value: ZxBarcodeError) -> Option<Self> {
+        match value {
+            ZxBarcodeError::None() => None,
+            ZxBarcodeError::Checksum(message) => Some(BarcodeError::Checksum(message)),
+            ZxBarcodeError::Format(message) => Some(BarcodeError::Format(message)),
+            ZxBarcodeError::Unsupported(message) => Some(BarcodeError::Unsupported(message)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Position {
     top_left: Point,
+    top_right: Point,
     bottom_right: Point,
+    bottom_left: Point,
 }
 
 impl Position {
@@ -230,153 +604,2135 @@ impl Position {
         self.top_left
     }
 
+    pub fn top_right(&self) -> Point {
+        self.top_right
+    }
+
     pub fn bottom_right(&self) -> Point {
         self.bottom_right
     }
+
+    pub fn bottom_left(&self) -> Point {
+        self.bottom_left
+    }
 }
 
+/// A barcode found by zxing, with everything zxing reports about it copied
+/// out when it is created, so results can be freely shared between threads.
+#[derive(Debug, Clone)]
 pub struct DecodeResult {
-    inner: Barcode,
-    cached_text: OnceCell<String>,
-    cached_position: OnceCell<Position>,
-    cached_format: OnceCell<BarcodeFormat>,
+    text_mode: TextMode,
+    text: String,
+    bytes: Vec<u8>,
+    bytes_eci: Vec<u8>,
+    position: Position,
+    format: ZxBarcodeFormat,
+    symbology_identifier: String,
+    add_on: Option<String>,
+    ec_level: Option<String>,
+    version: Option<String>,
+    reader_init: bool,
+    error: Option<BarcodeError>,
+    content_type: ContentType,
+    orientation: i32,
+    is_mirrored: bool,
+    is_inverted: bool,
+    sequence_size: i32,
+    sequence_index: i32,
+    sequence_id: String,
+    symbol: Option<BitMatrix>,
 }
 
 impl DecodeResult {
-    fn new(inner: Barcode) -> Self {
+    fn new(inner: Barcode, text_mode: TextMode) -> Self {
+        let extra = |key: &str| {
+            let value = inner.extra_with_key(key);
+            (!value.is_empty()).then_some(value)
+        };
+        let position = inner.position();
         Self {
-            inner,
-            cached_text: OnceCell::new(),
-            cached_position: OnceCell::new(),
-            cached_format: OnceCell::new(),
+            text_mode,
+            text: inner.text(),
+            bytes: inner.bytes(),
+            bytes_eci: inner.bytes_eci(),
+            position: Position {
+                top_left: position.top_left.into(),
+                top_right: position.top_right.into(),
+                bottom_right: position.bottom_right.into(),
+                bottom_left: position.bottom_left.into(),
+            },
+            format: inner.format(),
+            symbology_identifier: inner.symbology_identifier(),
+            add_on: extra("EanAddOn"),
+            ec_level: extra("ECLevel"),
+            version: extra("Version"),
+            reader_init: extra("ReaderInit").is_some(),
+            error: BarcodeError::from_zx(inner.error()),
+            content_type: inner.content_type().into(),
+            orientation: inner.orientation(),
+            is_mirrored: inner.is_mirrored(),
+            is_inverted: inner.is_inverted(),
+            sequence_size: inner.sequence_size(),
+            sequence_index: inner.sequence_index(),
+            sequence_id: inner.sequence_id(),
+            symbol: Self::read_symbol(&inner),
+        }
+    }
+
+    fn read_symbol(inner: &Barcode) -> Option<BitMatrix> {
+        // Linear codes carry no symbol and zxing fails to render an empty
+        // one, so they must not reach `to_image_with`.
+        if ZxBarcodeFormats::list(ZxBarcodeFormat::AllLinear).contains(inner.format()) {
+            return None;
         }
+        let image = inner
+            .to_image_with(&zxingcpp::write().scale(1).add_quiet_zones(false))
+            .ok()?;
+        BitMatrix::from_luma(
+            image.width() as usize,
+            image.height() as usize,
+            &image.data(),
+        )
     }
 
     pub fn text(&self) -> &str {
-        self.cached_text.get_or_init(|| self.inner.text())
+        &self.text
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn bytes_eci(&self) -> &[u8] {
+        &self.bytes_eci
+    }
+
+    /// The text without the add-on. The add-on is the last content bytes,
+    /// which every text mode renders as they are except for `Hex`.
+    pub fn main_text(&self) -> &str {
+        let text = self.text();
+        let Some(add_on) = self.add_on() else {
+            return text;
+        };
+        let add_on: Cow<str> = match self.text_mode {
+            TextMode::Hex => add_on.bytes().map(|b| format!(" {:02X}", b)).collect(),
+            _ => add_on.into(),
+        };
+        text.strip_suffix(&*add_on).unwrap_or(text)
+    }
+
+    pub fn add_on(&self) -> Option<&str> {
+        self.add_on.as_deref()
+    }
+
+    /// Error correction level as reported by the symbology, e.g. "H" for
+    /// QR Code or "70%" for Aztec. `None` for symbologies without one.
+    pub fn ec_level(&self) -> Option<&str> {
+        self.ec_level.as_deref()
+    }
+
+    /// Symbol version or size, e.g. "1" for QR Code, "M3" for Micro QR Code
+    /// or "12x12" for Data Matrix. `None` for symbologies without one.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    pub fn reader_init(&self) -> bool {
+        self.reader_init
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.error().is_none()
+    }
+
+    pub fn error(&self) -> Option<&BarcodeError> {
+        self.error.as_ref()
     }
 
     pub fn position(&self) -> Position {
-        *self.cached_position.get_or_init(|| {
-            let position = self.inner.position();
-            Position {
-                top_left: position.top_left.into(),
-                bottom_right: position.bottom_right.into(),
-            }
-        })
+        self.position
     }
 
     pub fn format(&self) -> Result<BarcodeFormat> {
-        if let Some(format) = self.cached_format.get() {
-            Ok(*format)
+        self.format.try_into()
+    }
+
+    pub fn symbology_identifier(&self) -> &str {
+        &self.symbology_identifier
+    }
+
+    pub fn content_type(&self) -> ContentType {
+        self.content_type
+    }
+
+    pub fn orientation(&self) -> i32 {
+        self.orientation
+    }
+
+    pub fn is_mirrored(&self) -> bool {
+        self.is_mirrored
+    }
+
+    pub fn is_inverted(&self) -> bool {
+        self.is_inverted
+    }
+
+    pub fn is_part_of_sequence(&self) -> bool {
+        self.sequence_size > -1 && self.sequence_index > -1
+    }
+
+    /// Number of symbols in the Structured Append sequence. `None` if the
+    /// symbol is not part of a sequence or the size is not encoded (e.g. a
+    /// Macro PDF417 segment other than the last one).
+    pub fn sequence_size(&self) -> Option<usize> {
+        if self.is_part_of_sequence() && self.sequence_size > 0 {
+            Some(self.sequence_size as usize)
         } else {
-            let format = self.inner.format().try_into()?;
-            let _ = self.cached_format.set(format);
-            Ok(format)
+            None
+        }
+    }
+
+    /// Zero-based position of the symbol in its Structured Append sequence.
+    pub fn sequence_index(&self) -> Option<usize> {
+        self.is_part_of_sequence()
+            .then_some(self.sequence_index as usize)
+    }
+
+    pub fn sequence_id(&self) -> Option<&str> {
+        self.is_part_of_sequence()
+            .then_some(self.sequence_id.as_str())
+    }
+
+    /// Owned copy of the sequence information and payload, for use with
+    /// [`reassemble`]. `None` if the symbol is not part of a sequence or
+    /// could not be decoded.
+    pub fn segment(&self) -> Option<Segment> {
+        if !self.is_valid() {
+            return None;
         }
+        Some(Segment {
+            format: self.format().ok()?,
+            id: self.sequence_id()?.to_owned(),
+            index: self.sequence_index()?,
+            size: self.sequence_size(),
+            text: self.text().to_owned(),
+            bytes: self.bytes().to_vec(),
+        })
+    }
+
+    /// Module matrix sampled from the image, one bit per module without quiet
+    /// zone. `None` for linear codes and for symbologies zxing does not keep
+    /// the sampled matrix for, e.g. PDF417.
+    pub fn symbol(&self) -> Option<&BitMatrix> {
+        self.symbol.as_ref()
     }
 
     pub fn points(&self) -> [Point; 4] {
         let position = self.position();
-        let top_left = position.top_left();
-        let bottom_right = position.bottom_right();
 
         [
-            top_left,
-            Point::new(bottom_right.x, top_left.y),
-            bottom_right,
-            Point::new(top_left.x, bottom_right.y),
+            position.top_left(),
+            position.top_right(),
+            position.bottom_right(),
+            position.bottom_left(),
         ]
     }
 }
 
-#[derive(Debug)]
-pub struct GrayImage<'a> {
-    raw: Cow<'a, [u8]>,
-    width: u32,
-    height: u32,
-    format: ImageFormat,
+/// A grid of modules packed eight per byte, most significant bit first, with
+/// each row starting on a byte boundary. Set bits are dark modules.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BitMatrix {
+    width: usize,
+    height: usize,
+    bits: Vec<u8>,
 }
 
-impl<'a> GrayImage<'a> {
-    pub fn new(raw: impl Into<Cow<'a, [u8]>>, width: u32, height: u32) -> Self {
-        let raw = raw.into();
-        if raw.len() != (width * height) as usize {
-            panic!("the length of the `raw` must be equal to `width` * `height`");
+impl BitMatrix {
+    fn from_luma(width: usize, height: usize, data: &[u8]) -> Option<Self> {
+        if width == 0 || height == 0 || data.len() != width * height {
+            return None;
         }
-        Self {
-            raw,
+        let row_stride = width.div_ceil(8);
+        let mut bits = vec![0u8; row_stride * height];
+        for (y, row) in data.chunks_exact(width).enumerate() {
+            for (x, &luma) in row.iter().enumerate() {
+                if luma < 128 {
+                    bits[y * row_stride + x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+        }
+        Some(Self {
             width,
             height,
-            format: ImageFormat::Lum,
-        }
+            bits,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
     }
+
+    /// Number of bytes per row in [`BitMatrix::as_bytes`].
+    pub fn row_stride(&self) -> usize {
+        self.width.div_ceil(8)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        assert!(x < self.width && y < self.height, "module out of bounds");
+        self.bits[y * self.row_stride() + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Segment {
+    format: BarcodeFormat,
+    id: String,
+    index: usize,
+    size: Option<usize>,
+    text: String,
+    bytes: Vec<u8>,
 }
 
-impl<'a> From<&'a GrayImage<'a>> for ImageView<'a> {
-    fn from(value: &'a GrayImage<'a>) -> Self {
-        ImageView::from_slice(&value.raw, value.width, value.height, value.format).unwrap()
+impl Segment {
+    pub fn format(&self) -> BarcodeFormat {
+        self.format
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
     }
 }
 
-fn decode<'a>(
-    image: GrayImage<'a>,
-    formats: &[BarcodeFormat],
-    multi: bool,
-) -> Result<Vec<Barcode>> {
-    let mut read_barcodes = if formats.is_empty() {
-        zxingcpp::read().formats(&[ZxBarcodeFormat::All])
-    } else {
-        let mut zx_formats_buf = [ZxBarcodeFormat::None; 32];
-        for (i, f) in formats.iter().enumerate() {
-            zx_formats_buf[i] = (*f).into();
-        }
-        zxingcpp::read().formats(&zx_formats_buf[..formats.len()])
-    };
+/// A message split across several symbols with Structured Append (QR Code,
+/// Data Matrix, Aztec) or Macro PDF417, merged from the segments read so far.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StructuredMessage {
+    format: BarcodeFormat,
+    id: String,
+    size: Option<usize>,
+    segments: Vec<Segment>,
+}
+
+impl StructuredMessage {
+    pub fn format(&self) -> BarcodeFormat {
+        self.format
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Total number of segments, if known from any of the segments read.
+    pub fn size(&self) -> Option<usize> {
+        self.size
+    }
+
+    /// Segments read so far, ordered by index.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Indices of the segments not read yet. If the size is unknown only the
+    /// gaps before the highest index read can be reported.
+    pub fn missing(&self) -> Vec<usize> {
+        let end = match self.size {
+            Some(size) => size,
+            None => self.segments.last().map_or(0, |s| s.index + 1),
+        };
+        (0..end)
+            .filter(|index| {
+                self.segments
+                    .binary_search_by_key(index, |s| s.index)
+                    .is_err()
+            })
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.size.is_some() && self.missing().is_empty()
+    }
+
+    /// Text of the segments read so far, concatenated in order.
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|s| s.text.as_str()).collect()
+    }
 
-    if !multi {
-        read_barcodes.set_max_number_of_symbols(1);
+    /// Bytes of the segments read so far, concatenated in order.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .flat_map(|s| s.bytes.iter().copied())
+            .collect()
     }
-    read_barcodes
-        .from(Into::<ImageView>::into(&image))
-        .map_err(|e| Error::DecodeError(e.to_string()))
 }
 
-pub fn decode_multiple<'a>(
-    image: GrayImage<'a>,
-    formats: &[BarcodeFormat],
-) -> Result<Vec<DecodeResult>> {
-    let barcodes = decode(image, formats, true)?;
-    Ok(barcodes
-        .into_iter()
-        .map(DecodeResult::new)
-        .collect::<Vec<_>>())
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Binarizer {
+    #[default]
+    LocalAverage,
+    GlobalHistogram,
+    FixedThreshold,
+    BoolCast,
 }
 
-pub fn decode_single<'a>(
-    image: GrayImage<'a>,
-    formats: &[BarcodeFormat],
-) -> Result<Option<DecodeResult>> {
-    match decode(image, formats, false) {
-        Ok(mut results) => {
-            if results.is_empty() {
-                Ok(None)
-            } else {
-                Ok(Some(DecodeResult::new(results.remove(0))))
-            }
+impl Display for Binarizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Binarizer::*;
+        let v = match self {
+            LocalAverage => "LocalAverage",
+            GlobalHistogram => "GlobalHistogram",
+            FixedThreshold => "FixedThreshold",
+            BoolCast => "BoolCast",
+        };
+        write!(f, "{}", v)
+    }
+}
+
+impl From<Binarizer> for ZxBinarizer {
+    fn from(value: Binarizer) -> Self {
+        use Binarizer::*;
+        match value {
+            LocalAverage => ZxBinarizer::LocalAverage,
+            GlobalHistogram => ZxBinarizer::GlobalHistogram,
+            FixedThreshold => ZxBinarizer::FixedThreshold,
+            BoolCast => ZxBinarizer::BoolCast,
         }
-        Err(e) => Err(e),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum TextMode {
+    Plain,
+    ECI,
+    #[default]
+    HRI,
+    Hex,
+    Escaped,
+}
 
-    #[test]
-    fn test_barcode_format_display() {
-        assert_eq!(format!("{}", BarcodeFormat::DataBarExp), "DataBarExpanded");
-        assert_eq!(format!("{}", BarcodeFormat::DataBarLtd), "DataBarLimited");
-        assert_eq!(format!("{}", BarcodeFormat::DataBarExpStk), "DataBarExpandedStacked");
+impl Display for TextMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use TextMode::*;
+        let v = match self {
+            Plain => "Plain",
+            ECI => "ECI",
+            HRI => "HRI",
+            Hex => "Hex",
+            Escaped => "Escaped",
+        };
+        write!(f, "{}", v)
+    }
+}
+
+impl From<TextMode> for ZxTextMode {
+    fn from(value: TextMode) -> Self {
+        use TextMode::*;
+        match value {
+            Plain => ZxTextMode::Plain,
+            ECI => ZxTextMode::ECI,
+            HRI => ZxTextMode::HRI,
+            Hex => ZxTextMode::Hex,
+            Escaped => ZxTextMode::Escaped,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum EanAddOnSymbol {
+    #[default]
+    Ignore,
+    Read,
+    Require,
+}
+
+impl Display for EanAddOnSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use EanAddOnSymbol::*;
+        let v = match self {
+            Ignore => "Ignore",
+            Read => "Read",
+            Require => "Require",
+        };
+        write!(f, "{}", v)
+    }
+}
+
+impl From<EanAddOnSymbol> for ZxEanAddOnSymbol {
+    fn from(value: EanAddOnSymbol) -> Self {
+        use EanAddOnSymbol::*;
+        match value {
+            Ignore => ZxEanAddOnSymbol::Ignore,
+            Read => ZxEanAddOnSymbol::Read,
+            Require => ZxEanAddOnSymbol::Require,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DecodeOptions {
+    binarizer: Binarizer,
+    text_mode: TextMode,
+    ean_add_on_symbol: EanAddOnSymbol,
+    pure: bool,
+    return_errors: bool,
+    try_harder: bool,
+    try_rotate: bool,
+    try_invert: bool,
+    try_downscale: bool,
+    channel: Channel,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            binarizer: Binarizer::default(),
+            text_mode: TextMode::default(),
+            ean_add_on_symbol: EanAddOnSymbol::default(),
+            pure: false,
+            return_errors: false,
+            try_harder: true,
+            try_rotate: true,
+            try_invert: true,
+            try_downscale: true,
+            channel: Channel::default(),
+        }
+    }
+}
+
+impl DecodeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_binarizer(mut self, binarizer: Binarizer) -> Self {
+        self.binarizer = binarizer;
+        self
+    }
+
+    pub fn with_text_mode(mut self, text_mode: TextMode) -> Self {
+        self.text_mode = text_mode;
+        self
+    }
+
+    pub fn with_ean_add_on_symbol(mut self, ean_add_on_symbol: EanAddOnSymbol) -> Self {
+        self.ean_add_on_symbol = ean_add_on_symbol;
+        self
+    }
+
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
+
+    pub fn with_return_errors(mut self, return_errors: bool) -> Self {
+        self.return_errors = return_errors;
+        self
+    }
+
+    pub fn with_try_harder(mut self, try_harder: bool) -> Self {
+        self.try_harder = try_harder;
+        self
+    }
+
+    pub fn with_try_rotate(mut self, try_rotate: bool) -> Self {
+        self.try_rotate = try_rotate;
+        self
+    }
+
+    pub fn with_try_invert(mut self, try_invert: bool) -> Self {
+        self.try_invert = try_invert;
+        self
+    }
+
+    pub fn with_try_downscale(mut self, try_downscale: bool) -> Self {
+        self.try_downscale = try_downscale;
+        self
+    }
+
+    /// How the colors of RGB(A) images become gray levels. Images are read
+    /// as they are with the default, [`Channel::Rec601`], and converted with
+    /// [`Image::to_gray`] otherwise.
+    pub fn with_channel(mut self, channel: Channel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    pub fn binarizer(&self) -> Binarizer {
+        self.binarizer
+    }
+
+    pub fn text_mode(&self) -> TextMode {
+        self.text_mode
+    }
+
+    pub fn ean_add_on_symbol(&self) -> EanAddOnSymbol {
+        self.ean_add_on_symbol
+    }
+
+    pub fn pure(&self) -> bool {
+        self.pure
+    }
+
+    pub fn return_errors(&self) -> bool {
+        self.return_errors
+    }
+
+    pub fn try_harder(&self) -> bool {
+        self.try_harder
+    }
+
+    pub fn try_rotate(&self) -> bool {
+        self.try_rotate
+    }
+
+    pub fn try_invert(&self) -> bool {
+        self.try_invert
+    }
+
+    pub fn try_downscale(&self) -> bool {
+        self.try_downscale
+    }
+
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+}
+
+/// Layout of the bytes of one pixel of an [`Image`]. Alpha, and the unused
+/// byte of `XRGB`, are ignored when the image is read.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ImageFormat {
+    Lum,
+    RGB,
+    BGR,
+    RGBA,
+    BGRA,
+    ARGB,
+    ABGR,
+    XRGB,
+}
+
+impl ImageFormat {
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            ImageFormat::Lum => 1,
+            ImageFormat::RGB | ImageFormat::BGR => 3,
+            _ => 4,
+        }
+    }
+
+    /// Offsets of the red, green and blue bytes within a pixel.
+    const fn rgb_offsets(self) -> Option<[usize; 3]> {
+        match self {
+            ImageFormat::Lum => None,
+            ImageFormat::RGB | ImageFormat::RGBA => Some([0, 1, 2]),
+            ImageFormat::BGR | ImageFormat::BGRA => Some([2, 1, 0]),
+            ImageFormat::ARGB | ImageFormat::XRGB => Some([1, 2, 3]),
+            ImageFormat::ABGR => Some([3, 2, 1]),
+        }
+    }
+}
+
+/// How a color is turned into the gray level zxing reads. Picking a single
+/// channel, or weights of one's own, keeps the contrast of e.g. red ink on
+/// a colored background that luma washes out.
+#[derive(Debug, Default, Clone, Copy)]
+pub enum Channel {
+    /// Luma with ITU-R BT.601 weights, which zxing uses for color images.
+    #[default]
+    Rec601,
+    /// Luma with ITU-R BT.709 weights.
+    Rec709,
+    Red,
+    Green,
+    Blue,
+    /// The brightest of the three channels.
+    Max,
+    /// The darkest of the three channels.
+    Min,
+    /// The sum of red, green and blue multiplied by these weights, rounded
+    /// and clamped to 0..=255.
+    Weights([f32; 3]),
+}
+
+impl Channel {
+    pub fn gray(self, r: u8, g: u8, b: u8) -> u8 {
+        let (r32, g32, b32) = (r as u32, g as u32, b as u32);
+        match self {
+            Channel::Rec601 => ((r32 * 19595 + g32 * 38470 + b32 * 7471 + 0x8000) >> 16) as u8,
+            Channel::Rec709 => ((r32 * 13933 + g32 * 46871 + b32 * 4732 + 0x8000) >> 16) as u8,
+            Channel::Red => r,
+            Channel::Green => g,
+            Channel::Blue => b,
+            Channel::Max => r.max(g).max(b),
+            Channel::Min => r.min(g).min(b),
+            // `as` saturates, and maps NaN to 0
+            Channel::Weights([wr, wg, wb]) => {
+                (r as f32 * wr + g as f32 * wg + b as f32 * wb).round() as u8
+            }
+        }
+    }
+}
+
+// Weights are compared bit for bit, so that equality is reflexive even for
+// NaN and `DecodeOptions` can be `Eq`.
+impl PartialEq for Channel {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Channel::Weights(a), Channel::Weights(b)) => {
+                a.map(f32::to_bits) == b.map(f32::to_bits)
+            }
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for Channel {}
+
+impl From<ImageFormat> for ZxImageFormat {
+    fn from(value: ImageFormat) -> Self {
+        match value {
+            ImageFormat::Lum => ZxImageFormat::Lum,
+            ImageFormat::RGB => ZxImageFormat::RGB,
+            ImageFormat::BGR => ZxImageFormat::BGR,
+            ImageFormat::RGBA => ZxImageFormat::RGBA,
+            ImageFormat::BGRA => ZxImageFormat::BGRA,
+            ImageFormat::ARGB => ZxImageFormat::ARGB,
+            ImageFormat::ABGR => ZxImageFormat::ABGR,
+            // zxing only reads the color channels, so the leading byte may
+            // hold anything.
+            ImageFormat::XRGB => ZxImageFormat::ARGB,
+        }
+    }
+}
+
+/// Pixels in one of the [`ImageFormat`]s, borrowed or owned. Rows start
+/// `row_stride` bytes apart and pixels within a row `pixel_stride` bytes
+/// apart, so padded rows, crops and single planes of interleaved data can be
+/// read in place.
+#[derive(Debug)]
+pub struct Image<'a> {
+    data: Cow<'a, [u8]>,
+    width: u32,
+    height: u32,
+    format: ImageFormat,
+    row_stride: usize,
+    pixel_stride: usize,
+}
+
+impl<'a> Image<'a> {
+    /// An image with tightly packed pixels and rows.
+    pub fn new(
+        data: impl Into<Cow<'a, [u8]>>,
+        width: u32,
+        height: u32,
+        format: ImageFormat,
+    ) -> Result<Self> {
+        let pixel_stride = format.bytes_per_pixel();
+        let row_stride = (width as usize)
+            .checked_mul(pixel_stride)
+            .ok_or_else(|| Error::InvalidInput("the image is too large".to_string()))?;
+        Self::with_strides(data, width, height, format, row_stride, pixel_stride)
+    }
+
+    pub fn with_strides(
+        data: impl Into<Cow<'a, [u8]>>,
+        width: u32,
+        height: u32,
+        format: ImageFormat,
+        row_stride: usize,
+        pixel_stride: usize,
+    ) -> Result<Self> {
+        let data = data.into();
+        let invalid = |message: &str| Error::InvalidInput(message.to_string());
+        let too_large = || invalid("the image is too large");
+
+        if width == 0 || height == 0 {
+            return Err(invalid("the image must not be empty"));
+        }
+        // zxing indexes pixels with C ints.
+        let pixels = (width as u64) * (height as u64);
+        if pixels > i32::MAX as u64
+            || i32::try_from(row_stride).is_err()
+            || i32::try_from(pixel_stride).is_err()
+        {
+            return Err(too_large());
+        }
+        let pixel_size = format.bytes_per_pixel();
+        if pixel_stride < pixel_size {
+            return Err(invalid("the pixel stride is smaller than a pixel"));
+        }
+        let row_size = (width as usize - 1)
+            .checked_mul(pixel_stride)
+            .and_then(|n| n.checked_add(pixel_size))
+            .ok_or_else(too_large)?;
+        if row_stride < row_size {
+            return Err(invalid("the row stride is smaller than a row"));
+        }
+        let size = (height as usize - 1)
+            .checked_mul(row_stride)
+            .and_then(|n| n.checked_add(row_size))
+            .ok_or_else(too_large)?;
+        if data.len() < size {
+            return Err(Error::InvalidInput(format!(
+                "expected at least {} bytes of pixel data, got {}",
+                size,
+                data.len()
+            )));
+        }
+
+        Ok(Self {
+            data,
+            width,
+            height,
+            format,
+            row_stride,
+            pixel_stride,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    pub fn row_stride(&self) -> usize {
+        self.row_stride
+    }
+
+    pub fn pixel_stride(&self) -> usize {
+        self.pixel_stride
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The image as tightly packed [`ImageFormat::Lum`] pixels, with colors
+    /// turned into gray levels by `channel`.
+    pub fn to_gray(&self, channel: Channel) -> Image<'static> {
+        let pixels = (0..self.height as usize).flat_map(|y| {
+            (0..self.width as usize).map(move |x| {
+                let start = y * self.row_stride + x * self.pixel_stride;
+                &self.data[start..start + self.format.bytes_per_pixel()]
+            })
+        });
+        let gray: Vec<u8> = match self.format.rgb_offsets() {
+            None => pixels.map(|p| p[0]).collect(),
+            Some([r, g, b]) => pixels.map(|p| channel.gray(p[r], p[g], p[b])).collect(),
+        };
+        Image {
+            data: Cow::Owned(gray),
+            width: self.width,
+            height: self.height,
+            format: ImageFormat::Lum,
+            row_stride: self.width as usize,
+            pixel_stride: 1,
+        }
+    }
+}
+
+/// A tightly packed 8-bit grayscale image, kept for code written before
+/// [`Image`] took other formats and strides.
+#[deprecated(note = "use `Image::new(data, width, height, ImageFormat::Lum)`")]
+#[derive(Debug)]
+pub struct GrayImage<'a>(Image<'a>);
+
+#[allow(deprecated)]
+impl<'a> GrayImage<'a> {
+    /// # Panics
+    ///
+    /// Panics if the length of `raw` is not `width * height` or the image is
+    /// empty or too large for zxing. [`Image::new`] returns an error instead.
+    pub fn new(raw: impl Into<Cow<'a, [u8]>>, width: u32, height: u32) -> Self {
+        let raw = raw.into();
+        if raw.len() as u64 != width as u64 * height as u64 {
+            panic!("the length of the `raw` must be equal to `width` * `height`");
+        }
+        match Image::new(raw, width, height, ImageFormat::Lum) {
+            Ok(image) => Self(image),
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+#[allow(deprecated)]
+impl<'a> From<GrayImage<'a>> for Image<'a> {
+    fn from(value: GrayImage<'a>) -> Self {
+        value.0
+    }
+}
+
+#[allow(deprecated)]
+impl IntoImage for GrayImage<'static> {
+    type Error = Error;
+
+    fn read_with<R: Send>(self, read: impl FnOnce(Image<'_>) -> R + Send) -> Result<R> {
+        Ok(read(self.0))
+    }
+}
+
+impl<'a> From<&'a Image<'a>> for ImageView<'a> {
+    fn from(value: &'a Image<'a>) -> Self {
+        // SAFETY: `Image::with_strides` checked that every pixel lies within
+        // `data`, and that all sizes fit the C ints zxing takes, so this
+        // cannot fail either.
+        unsafe {
+            ImageView::from_ptr(
+                value.data.as_ptr(),
+                value.width,
+                value.height,
+                value.format.into(),
+                value.row_stride,
+                value.pixel_stride,
+            )
+        }
+        .unwrap()
+    }
+}
+
+/// An image for [`Reader::read_batch`], produced on the worker thread that
+/// reads it, so that e.g. decoding image files also runs in parallel.
+pub trait IntoImage: Send + 'static {
+    type Error: From<Error> + Send + 'static;
+
+    /// Calls `read` with the image, which may borrow data that only lives
+    /// for the duration of the call.
+    fn read_with<R: Send>(
+        self,
+        read: impl FnOnce(Image<'_>) -> R + Send,
+    ) -> std::result::Result<R, Self::Error>;
+}
+
+impl IntoImage for Image<'static> {
+    type Error = Error;
+
+    fn read_with<R: Send>(self, read: impl FnOnce(Image<'_>) -> R + Send) -> Result<R> {
+        Ok(read(self))
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    static IN_POOL: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Worker threads shared by every batch and by [`spawn`], one per available
+/// core to begin with, or as many as the largest batch asked for. Threads
+/// are started on demand and kept for the life of the process.
+struct ThreadPool {
+    jobs: mpsc::Sender<Job>,
+    queue: Arc<Mutex<mpsc::Receiver<Job>>>,
+    threads: Mutex<usize>,
+}
+
+impl ThreadPool {
+    fn shared() -> &'static ThreadPool {
+        static POOL: OnceLock<ThreadPool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let (jobs, queue) = mpsc::channel::<Job>();
+            let pool = ThreadPool {
+                jobs,
+                queue: Arc::new(Mutex::new(queue)),
+                threads: Mutex::new(0),
+            };
+            pool.grow(thread::available_parallelism().map_or(1, |n| n.get()));
+            pool
+        })
+    }
+
+    fn threads(&self) -> usize {
+        *self.threads.lock().unwrap()
+    }
+
+    /// Starts threads until there are at least `threads`.
+    fn grow(&self, threads: usize) {
+        let mut started = self.threads.lock().unwrap();
+        while *started < threads {
+            let queue = self.queue.clone();
+            thread::Builder::new()
+                .name(format!("reader_core-{}", *started))
+                .spawn(move || {
+                    IN_POOL.set(true);
+                    loop {
+                        let job = queue.lock().unwrap().recv();
+                        let Ok(job) = job else {
+                            break;
+                        };
+                        // A panicking job must not take the thread with it.
+                        let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                    }
+                })
+                .expect("failed to start a worker thread");
+            *started += 1;
+        }
+    }
+
+    fn execute(&self, job: Job) {
+        if IN_POOL.get() {
+            // Waiting for a job queued behind the current one could deadlock.
+            job();
+        } else {
+            self.queue(job);
+        }
+    }
+
+    /// Puts `job` at the end of the line, even from a worker thread.
+    fn queue(&self, job: Job) {
+        self.jobs.send(job).expect("the worker threads are gone");
+    }
+}
+
+/// One [`Reader::read_batch`] call. Its workers read one image per job and
+/// then queue themselves again behind whatever was queued meanwhile, e.g.
+/// [`spawn`]ed async reads, so that a long batch does not hold the pool's
+/// threads until it is done.
+struct Batch<I: IntoImage, T> {
+    images: Mutex<BatchImages<T>>,
+    reader: Reader,
+    results: mpsc::Sender<(usize, BatchResult<I>)>,
+}
+
+type BatchResult<I> = std::result::Result<Vec<DecodeResult>, <I as IntoImage>::Error>;
+
+/// The images of a batch and how many were taken. An iterator that panics
+/// is not asked again; the image it failed to produce counts as taken, so
+/// that it gets an error entry like any other.
+struct BatchImages<T> {
+    iter: T,
+    taken: usize,
+    done: bool,
+}
+
+impl<T: Iterator> BatchImages<T> {
+    /// The index of the next image and the image, `None` if taking it
+    /// panicked.
+    fn next(&mut self) -> Option<(usize, Option<T::Item>)> {
+        if self.done {
+            return None;
+        }
+        let i = self.taken;
+        match panic::catch_unwind(AssertUnwindSafe(|| self.iter.next())) {
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Ok(Some(image)) => {
+                self.taken += 1;
+                Some((i, Some(image)))
+            }
+            Err(_) => {
+                self.done = true;
+                self.taken += 1;
+                Some((i, None))
+            }
+        }
+    }
+}
+
+impl<I, T> Batch<I, T>
+where
+    I: IntoImage,
+    T: Iterator<Item = I> + Send + 'static,
+{
+    /// Reads the next image, if any is left, and reports whether it did.
+    fn step(&self) -> bool {
+        let next = self
+            .images
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .next();
+        let Some((i, image)) = next else {
+            return false;
+        };
+        let panicked = |what: &str| Error::DecodeError(format!("{} panicked", what));
+        let result = match image {
+            Some(image) => panic::catch_unwind(AssertUnwindSafe(|| {
+                image.read_with(|image| self.reader.read_all(image))
+            }))
+            .unwrap_or_else(|_| Ok(Err(panicked("reading the image"))))
+            .and_then(|result| result.map_err(Into::into)),
+            None => Err(panicked("taking the image from the iterator").into()),
+        };
+        let _ = self.results.send((i, result));
+        true
+    }
+
+    fn work(self: Arc<Self>) {
+        if self.step() {
+            ThreadPool::shared().queue(Box::new(move || self.work()));
+        }
+    }
+}
+
+/// Runs `job` on the worker threads [`Reader::read_batch`] uses, so that
+/// callers need not start threads of their own. Jobs wait in line while all
+/// threads are busy; one that panics does not affect the others.
+pub fn spawn(job: impl FnOnce() + Send + 'static) {
+    ThreadPool::shared().execute(Box::new(job));
+}
+
+/// Decodes images with a fixed set of formats and options, which are
+/// converted for zxing once instead of on every call.
+#[derive(Debug, Clone)]
+pub struct Reader {
+    formats: BarcodeFormats,
+    zx_formats: Vec<ZxBarcodeFormat>,
+    options: DecodeOptions,
+}
+
+impl Default for Reader {
+    fn default() -> Self {
+        Self::new(BarcodeFormats::ANY, DecodeOptions::default())
+    }
+}
+
+impl Reader {
+    pub fn new(formats: impl Into<BarcodeFormats>, options: DecodeOptions) -> Self {
+        let formats = formats.into();
+        Self {
+            formats,
+            zx_formats: formats.to_zx(),
+            options,
+        }
+    }
+
+    pub fn formats(&self) -> BarcodeFormats {
+        self.formats
+    }
+
+    pub fn options(&self) -> &DecodeOptions {
+        &self.options
+    }
+
+    fn decode(&self, image: &Image<'_>, multi: bool) -> Result<Vec<Barcode>> {
+        if self.zx_formats.is_empty() {
+            // zxing would read every format when given none.
+            return Ok(Vec::new());
+        }
+        let options = &self.options;
+        let mut read_barcodes = zxingcpp::read().formats(&self.zx_formats);
+
+        read_barcodes
+            .set_binarizer(options.binarizer)
+            .set_text_mode(options.text_mode)
+            .set_ean_add_on_symbol(options.ean_add_on_symbol)
+            .set_is_pure(options.pure)
+            .set_return_errors(options.return_errors)
+            .set_try_harder(options.try_harder)
+            .set_try_rotate(options.try_rotate)
+            .set_try_invert(options.try_invert)
+            .set_try_downscale(options.try_downscale);
+
+        if !multi {
+            read_barcodes.set_max_number_of_symbols(1);
+        }
+        // zxing reads color with the default weights itself.
+        let gray;
+        let image = if options.channel != Channel::default() && image.format.rgb_offsets().is_some()
+        {
+            gray = image.to_gray(options.channel);
+            &gray
+        } else {
+            image
+        };
+        let mut barcodes = read_barcodes
+            .from(Into::<ImageView>::into(image))
+            .map_err(|e| Error::DecodeError(e.to_string()))?;
+
+        if options.pure {
+            // In pure mode zxing returns an empty placeholder barcode when nothing was found.
+            barcodes.retain(|b| b.format() != ZxBarcodeFormat::None);
+            if barcodes.is_empty() {
+                return Err(Error::DecodeError(
+                    "no symbol found in pure mode; the image must contain exactly one unrotated symbol with a quiet zone and nothing else".to_string(),
+                ));
+            }
+        }
+        Ok(barcodes)
+    }
+
+    pub fn read_all<'a>(&self, image: impl Into<Image<'a>>) -> Result<Vec<DecodeResult>> {
+        let image = image.into();
+        let barcodes = self.decode(&image, true)?;
+        Ok(barcodes
+            .into_iter()
+            .map(|barcode| DecodeResult::new(barcode, self.options.text_mode))
+            .collect::<Vec<_>>())
+    }
+
+    pub fn read<'a>(&self, image: impl Into<Image<'a>>) -> Result<Option<DecodeResult>> {
+        let image = image.into();
+        let mut barcodes = self.decode(&image, false)?;
+        if barcodes.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(DecodeResult::new(
+                barcodes.remove(0),
+                self.options.text_mode,
+            )))
+        }
+    }
+
+    /// Reads all barcodes from each image on `workers` threads of the shared
+    /// pool, which grows to that many threads if it has fewer, or on all of
+    /// them if `workers` is 0. Threads take the next image from `images` once
+    /// done with the last one, so only the images being read are held in
+    /// memory, and jobs queued with [`spawn`] meanwhile run in between.
+    ///
+    /// Results are in the order of `images`, one per image. A failing image
+    /// only affects its own entry; if `images` panics, the image it failed
+    /// to produce gets an error entry and the batch ends there.
+    pub fn read_batch<I, T>(
+        &self,
+        images: T,
+        workers: usize,
+    ) -> Vec<std::result::Result<Vec<DecodeResult>, I::Error>>
+    where
+        I: IntoImage,
+        T: IntoIterator<Item = I>,
+        T::IntoIter: Send + 'static,
+    {
+        let pool = ThreadPool::shared();
+        let workers = match workers {
+            0 => pool.threads(),
+            n => {
+                pool.grow(n);
+                n
+            }
+        };
+        let (sender, receiver) = mpsc::channel();
+        let batch = Arc::new(Batch {
+            images: Mutex::new(BatchImages {
+                iter: images.into_iter(),
+                taken: 0,
+                done: false,
+            }),
+            reader: self.clone(),
+            results: sender,
+        });
+
+        if IN_POOL.get() {
+            // Called from a job: the other threads may all be waiting, too.
+            while batch.step() {}
+        } else {
+            for _ in 0..workers {
+                let batch = batch.clone();
+                pool.queue(Box::new(move || batch.work()));
+            }
+        }
+        // The results end once the last worker lets go of the batch.
+        drop(batch);
+
+        let mut results = receiver.into_iter().collect::<Vec<_>>();
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+pub fn decode_multiple<'a>(
+    image: impl Into<Image<'a>>,
+    formats: impl Into<BarcodeFormats>,
+    options: &DecodeOptions,
+) -> Result<Vec<DecodeResult>> {
+    Reader::new(formats, *options).read_all(image)
+}
+
+pub fn decode_single<'a>(
+    image: impl Into<Image<'a>>,
+    formats: impl Into<BarcodeFormats>,
+    options: &DecodeOptions,
+) -> Result<Option<DecodeResult>> {
+    Reader::new(formats, *options).read(image)
+}
+
+/// See [`Reader::read_batch`].
+pub fn decode_batch<I, T>(
+    images: T,
+    formats: impl Into<BarcodeFormats>,
+    options: &DecodeOptions,
+    workers: usize,
+) -> Vec<std::result::Result<Vec<DecodeResult>, I::Error>>
+where
+    I: IntoImage,
+    T: IntoIterator<Item = I>,
+    T::IntoIter: Send + 'static,
+{
+    Reader::new(formats, *options).read_batch(images, workers)
+}
+
+/// Groups segments by format and sequence id and merges each group into a
+/// message. Segments read more than once are only counted once. Messages are
+/// returned in the order their first segment appears.
+pub fn reassemble(segments: impl IntoIterator<Item = Segment>) -> Vec<StructuredMessage> {
+    let mut messages: Vec<StructuredMessage> = Vec::new();
+    for segment in segments {
+        let message = match messages
+            .iter_mut()
+            .position(|m| m.format == segment.format && m.id == segment.id)
+        {
+            Some(i) => &mut messages[i],
+            None => {
+                messages.push(StructuredMessage {
+                    format: segment.format,
+                    id: segment.id.clone(),
+                    size: None,
+                    segments: Vec::new(),
+                });
+                messages.last_mut().unwrap()
+            }
+        };
+        if message.size.is_none() {
+            message.size = segment.size;
+        }
+        if let Err(i) = message
+            .segments
+            .binary_search_by_key(&segment.index, |s| s.index)
+        {
+            message.segments.insert(i, segment);
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_barcode_format_display() {
+        assert_eq!(format!("{}", BarcodeFormat::DataBarExp), "DataBarExpanded");
+        assert_eq!(format!("{}", BarcodeFormat::DataBarLtd), "DataBarLimited");
+        assert_eq!(format!("{}", BarcodeFormat::DataBarExpStk), "DataBarExpandedStacked");
+    }
+
+    #[test]
+    fn test_barcode_format_from_str() {
+        for format in BarcodeFormat::ALL {
+            assert_eq!(format.to_string().parse::<BarcodeFormat>().unwrap(), format);
+        }
+        assert_eq!(
+            "DataBarExpanded".parse::<BarcodeFormat>().unwrap(),
+            BarcodeFormat::DataBarExp
+        );
+        assert!(matches!(
+            "Typo".parse::<BarcodeFormat>(),
+            Err(Error::UnsupportedFormat(_))
+        ));
+        assert!("DataBarExp".parse::<BarcodeFormat>().is_err());
+    }
+
+    #[test]
+    fn test_barcode_formats() {
+        assert_eq!(
+            BarcodeFormats::LINEAR_CODES | BarcodeFormats::MATRIX_CODES,
+            BarcodeFormats::ANY
+        );
+        assert!(BarcodeFormats::LINEAR_CODES.contains(BarcodeFormats::RETAIL_CODES));
+        assert_eq!(BarcodeFormats::ANY.iter().count(), BarcodeFormat::ALL.len());
+        assert!(BarcodeFormats::default().is_empty());
+
+        let formats = BarcodeFormat::QRCode | BarcodeFormat::EAN13;
+        assert!(formats.contains(BarcodeFormat::MicroQRCode.into()));
+        assert!(!formats.contains(BarcodeFormat::EANUPC.into()));
+        assert_eq!(
+            formats,
+            [BarcodeFormat::EAN13, BarcodeFormat::QRCode]
+                .into_iter()
+                .collect()
+        );
+
+        let formats = BarcodeFormats::ANY - BarcodeFormat::DataBar;
+        assert!(
+            formats
+                .iter()
+                .all(|f| !f.to_string().starts_with("DataBar"))
+        );
+        assert_eq!(!formats, BarcodeFormat::DataBar.into());
+
+        let formats = BarcodeFormats::from(BarcodeFormat::QRCode) - BarcodeFormat::MicroQRCode;
+        assert!(!formats.contains(BarcodeFormat::QRCode.into()));
+        assert_eq!(
+            formats.to_zx(),
+            [
+                ZxBarcodeFormat::QRCodeModel1,
+                ZxBarcodeFormat::QRCodeModel2,
+                ZxBarcodeFormat::RMQRCode
+            ]
+        );
+        assert!(BarcodeFormats::empty().to_zx().is_empty());
+        assert_eq!(BarcodeFormats::from(&[]), BarcodeFormats::ANY);
+        assert_eq!(
+            BarcodeFormats::from(BarcodeFormat::QRCode) & BarcodeFormat::EAN13,
+            BarcodeFormats::empty()
+        );
+
+        assert_eq!(BarcodeFormats::from_bits(1 << 63), None);
+        assert_eq!(
+            BarcodeFormats::from_bits(BarcodeFormats::RETAIL_CODES.bits()),
+            Some(BarcodeFormats::RETAIL_CODES)
+        );
+    }
+
+    #[test]
+    fn test_decode_with_formats() {
+        let options = DecodeOptions::default();
+        let image = || render(ZxBarcodeFormat::QRCode, "hello");
+
+        let formats = BarcodeFormats::ANY - BarcodeFormat::QRCode;
+        assert!(decode_single(image(), formats, &options).unwrap().is_none());
+
+        let formats = BarcodeFormats::from(BarcodeFormat::QRCode) - BarcodeFormat::MicroQRCode;
+        assert!(decode_single(image(), formats, &options).unwrap().is_some());
+
+        // an empty intersection reads nothing, an empty slice everything
+        let formats = BarcodeFormats::from(BarcodeFormat::QRCode) & BarcodeFormat::EAN13;
+        assert!(decode_single(image(), formats, &options).unwrap().is_none());
+        let formats = (BarcodeFormat::QRCode | BarcodeFormat::EAN13)
+            - BarcodeFormat::QRCode
+            - BarcodeFormat::EAN13;
+        assert!(
+            decode_multiple(image(), formats, &options)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(decode_single(image(), &[], &options).unwrap().is_some());
+
+        // all formats given at once, more than zxing's former 32-slot buffer held
+        let result = decode_single(image(), &BarcodeFormat::ALL, &options).unwrap();
+        assert_eq!(result.unwrap().format().unwrap(), BarcodeFormat::QRCode);
+    }
+
+    #[test]
+    fn test_reader() {
+        let options = DecodeOptions::default().with_try_rotate(false);
+        let reader = Reader::new(BarcodeFormat::QRCode, options);
+        assert_eq!(reader.formats(), BarcodeFormat::QRCode.into());
+        assert_eq!(reader.options(), &options);
+
+        for content in ["first", "second", "third"] {
+            let image = render(ZxBarcodeFormat::QRCode, content);
+            let result = reader.read(image).unwrap().unwrap();
+            assert_eq!(result.text(), content);
+        }
+
+        let image = render(ZxBarcodeFormat::Code128, "hello");
+        assert!(reader.read_all(image).unwrap().is_empty());
+
+        let image = render(ZxBarcodeFormat::Code128, "hello");
+        let results = Reader::default().read_all(image).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].text(), "hello");
+    }
+
+    #[test]
+    fn test_decode_result_shared_between_threads() {
+        let image = render(ZxBarcodeFormat::QRCode, "shared");
+        let result = decode_single(image, &[], &DecodeOptions::default())
+            .unwrap()
+            .unwrap();
+
+        // moved to another thread, then read from several at once
+        let result = thread::spawn(move || result).join().unwrap();
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    assert_eq!(result.text(), "shared");
+                    assert!(!result.reader_init());
+                    assert!(result.is_valid());
+                    assert_eq!(result.bytes(), b"shared");
+                    assert_eq!(result.format().unwrap(), BarcodeFormat::QRCode);
+                    assert!(result.symbol().is_some());
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_image_formats_and_strides() {
+        let gray = render(ZxBarcodeFormat::QRCode, "pixels");
+        let (width, height) = (gray.width() as usize, gray.height() as usize);
+
+        for format in [
+            ImageFormat::Lum,
+            ImageFormat::RGB,
+            ImageFormat::BGR,
+            ImageFormat::RGBA,
+            ImageFormat::BGRA,
+            ImageFormat::ARGB,
+            ImageFormat::ABGR,
+            ImageFormat::XRGB,
+        ] {
+            let pixel_size = format.bytes_per_pixel();
+            // The alpha or unused byte is zero and must be ignored.
+            let alpha = match format {
+                ImageFormat::RGBA | ImageFormat::BGRA => Some(3),
+                ImageFormat::ARGB | ImageFormat::ABGR | ImageFormat::XRGB => Some(0),
+                _ => None,
+            };
+            for (pixel_stride, padding) in [(pixel_size, 0), (pixel_size + 1, 0), (pixel_size, 7)] {
+                let row_stride = width * pixel_stride + padding;
+                let mut data = vec![0; row_stride * height];
+                for y in 0..height {
+                    for x in 0..width {
+                        let offset = y * row_stride + x * pixel_stride;
+                        let value = gray.data()[y * width + x];
+                        for c in (0..pixel_size).filter(|c| Some(*c) != alpha) {
+                            data[offset + c] = value;
+                        }
+                    }
+                }
+
+                let image = Image::with_strides(
+                    data,
+                    width as u32,
+                    height as u32,
+                    format,
+                    row_stride,
+                    pixel_stride,
+                )
+                .unwrap();
+                let result = decode_single(image, &[], &DecodeOptions::default())
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(result.text(), "pixels", "{:?}", format);
+            }
+        }
+
+        // a crop of the same buffer, read in place
+        let crop =
+            Image::with_strides(&gray.data()[1..], 5, 5, ImageFormat::Lum, width, 1).unwrap();
+        assert_eq!(crop.width(), 5);
+        assert_eq!(crop.row_stride(), width);
+    }
+
+    #[test]
+    fn test_channel_gray() {
+        let (r, g, b) = (200, 100, 50);
+        assert_eq!(Channel::Rec601.gray(r, g, b), 124);
+        assert_eq!(Channel::Rec709.gray(r, g, b), 118);
+        assert_eq!(Channel::Red.gray(r, g, b), 200);
+        assert_eq!(Channel::Green.gray(r, g, b), 100);
+        assert_eq!(Channel::Blue.gray(r, g, b), 50);
+        assert_eq!(Channel::Max.gray(r, g, b), 200);
+        assert_eq!(Channel::Min.gray(r, g, b), 50);
+        assert_eq!(Channel::Weights([0.5, 0.5, 0.0]).gray(r, g, b), 150);
+        assert_eq!(Channel::Weights([2.0, 0.0, 0.0]).gray(r, g, b), 255);
+        assert_eq!(Channel::Weights([0.0, -1.0, 0.0]).gray(r, g, b), 0);
+        assert_eq!(Channel::Weights([f32::NAN, 0.0, 0.0]).gray(r, g, b), 0);
+        for channel in [Channel::Rec601, Channel::Rec709] {
+            assert_eq!(channel.gray(0, 0, 0), 0);
+            assert_eq!(channel.gray(255, 255, 255), 255);
+        }
+    }
+
+    #[test]
+    fn test_image_to_gray() {
+        let gray = render(ZxBarcodeFormat::QRCode, "red ink");
+        let (width, height) = (gray.width() as usize, gray.height() as usize);
+
+        // Red modules on a green background of the same luma, in BGR with
+        // padded rows.
+        let row_stride = width * 3 + 5;
+        let mut data = vec![0; row_stride * height];
+        for y in 0..height {
+            for x in 0..width {
+                let offset = y * row_stride + x * 3;
+                let bgr = if gray.data()[y * width + x] < 128 {
+                    [0, 0, 255]
+                } else {
+                    [0, 130, 0]
+                };
+                data[offset..offset + 3].copy_from_slice(&bgr);
+            }
+        }
+        let image = || {
+            Image::with_strides(
+                &data[..],
+                width as u32,
+                height as u32,
+                ImageFormat::BGR,
+                row_stride,
+                3,
+            )
+            .unwrap()
+        };
+        let options = DecodeOptions::default().with_try_invert(false);
+
+        // read in place with the default channel, converted with any other
+        assert!(decode_single(image(), &[], &options).unwrap().is_none());
+        let green = options.with_channel(Channel::Green);
+        let result = decode_single(image(), &[], &green).unwrap().unwrap();
+        assert_eq!(result.text(), "red ink");
+
+        let luma = image().to_gray(Channel::Rec601);
+        assert_eq!(luma.format(), ImageFormat::Lum);
+        assert_eq!(luma.row_stride(), width);
+        assert!(luma.data().iter().all(|v| *v == 76));
+        assert!(decode_single(luma, &[], &options).unwrap().is_none());
+
+        let green = image().to_gray(Channel::Green);
+        assert!(
+            green
+                .data()
+                .iter()
+                .zip(gray.data())
+                .all(|(g, v)| *g == if *v < 128 { 0 } else { 130 })
+        );
+        let result = decode_single(green, &[], &options).unwrap().unwrap();
+        assert_eq!(result.text(), "red ink");
+
+        let gray_again = gray.to_gray(Channel::Red);
+        assert_eq!(gray_again.data(), gray.data());
+    }
+
+    #[test]
+    fn test_image_invalid_input() {
+        let data = [0; 100];
+        let invalid = |result: Result<Image>| matches!(result, Err(Error::InvalidInput(_)));
+
+        assert!(Image::new(&data[..], 10, 10, ImageFormat::Lum).is_ok());
+        assert!(invalid(Image::new(&data[..], 10, 11, ImageFormat::Lum)));
+        assert!(invalid(Image::new(&data[..], 10, 10, ImageFormat::RGB)));
+        assert!(invalid(Image::new(&data[..], 0, 10, ImageFormat::Lum)));
+        assert!(invalid(Image::with_strides(
+            &data[..],
+            10,
+            2,
+            ImageFormat::RGB,
+            30,
+            2
+        )));
+        assert!(invalid(Image::with_strides(
+            &data[..],
+            10,
+            2,
+            ImageFormat::Lum,
+            9,
+            1
+        )));
+        assert!(Image::with_strides(&data[..], 10, 2, ImageFormat::Lum, 90, 1).is_ok());
+        assert!(invalid(Image::with_strides(
+            &data[..],
+            10,
+            2,
+            ImageFormat::Lum,
+            91,
+            1
+        )));
+
+        // sizes that overflow or exceed what zxing can address
+        assert!(invalid(Image::new(
+            &data[..],
+            u32::MAX,
+            u32::MAX,
+            ImageFormat::RGBA
+        )));
+        assert!(invalid(Image::new(
+            &data[..],
+            65536,
+            65536,
+            ImageFormat::Lum
+        )));
+        let huge = usize::MAX / 2;
+        assert!(invalid(Image::with_strides(
+            &data[..],
+            10,
+            10,
+            ImageFormat::Lum,
+            huge,
+            1
+        )));
+        assert!(invalid(Image::with_strides(
+            &data[..],
+            10,
+            10,
+            ImageFormat::Lum,
+            10,
+            huge
+        )));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_gray_image() {
+        let image = render(ZxBarcodeFormat::QRCode, "gray");
+        let (width, height) = (image.width(), image.height());
+        let gray = GrayImage::new(image.data.into_owned(), width, height);
+        let result = decode_single(gray, &[], &DecodeOptions::default()).unwrap();
+        assert_eq!(result.unwrap().text(), "gray");
+
+        let wrong_length = std::panic::catch_unwind(|| GrayImage::new(vec![0; 10], 3, 3));
+        assert!(wrong_length.is_err());
+    }
+
+    #[test]
+    fn test_decode_batch() {
+        let contents = (0..10).map(|i| format!("item {}", i)).collect::<Vec<_>>();
+        let images = || {
+            let mut images = contents
+                .iter()
+                .map(|content| render(ZxBarcodeFormat::QRCode, content))
+                .collect::<Vec<_>>();
+            images.insert(
+                3,
+                Image::new(vec![255; 64 * 64], 64, 64, ImageFormat::Lum).unwrap(),
+            );
+            images
+        };
+
+        for workers in [0, 1, 4, 32] {
+            let results = decode_batch(images(), &[], &DecodeOptions::default(), workers);
+            assert_eq!(results.len(), 11);
+            let texts = results
+                .iter()
+                .map(|r| {
+                    r.as_ref()
+                        .unwrap()
+                        .iter()
+                        .map(|r| r.text())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            assert!(texts[3].is_empty());
+            let mut expected = contents
+                .iter()
+                .map(|c| vec![c.as_str()])
+                .collect::<Vec<_>>();
+            expected.insert(3, vec![]);
+            assert_eq!(texts, expected);
+        }
+
+        // the failure of one image does not affect the others
+        let options = DecodeOptions::default().with_pure(true);
+        let images = vec![
+            render(ZxBarcodeFormat::QRCode, "pure"),
+            Image::new(vec![255; 64 * 64], 64, 64, ImageFormat::Lum).unwrap(),
+        ];
+        let results = decode_batch(images, &[], &options, 2);
+        assert_eq!(results[0].as_ref().unwrap()[0].text(), "pure");
+        assert!(matches!(results[1], Err(Error::DecodeError(_))));
+        assert!(decode_batch(Vec::<Image>::new(), &[], &options, 0).is_empty());
+
+        // a panic while producing an image is reported for that image only
+        enum TestImage {
+            Image(Image<'static>),
+            Panic,
+        }
+        impl IntoImage for TestImage {
+            type Error = Error;
+
+            fn read_with<R: Send>(self, read: impl FnOnce(Image<'_>) -> R + Send) -> Result<R> {
+                match self {
+                    TestImage::Image(image) => Ok(read(image)),
+                    TestImage::Panic => panic!("no image"),
+                }
+            }
+        }
+        let images = (0..4).map(|i| match i {
+            2 => TestImage::Panic,
+            _ => TestImage::Image(render(ZxBarcodeFormat::QRCode, "lazy")),
+        });
+        let results = decode_batch(images, &[], &DecodeOptions::default(), 0);
+        assert_eq!(results.len(), 4);
+        assert!(matches!(results[2], Err(Error::DecodeError(_))));
+        for i in [0, 1, 3] {
+            assert_eq!(results[i].as_ref().unwrap()[0].text(), "lazy");
+        }
+
+        // an iterator that panics ends the batch with an entry of its own
+        let images = (0..4).map(|i| match i {
+            2 => panic!("no more images"),
+            _ => render(ZxBarcodeFormat::QRCode, "lazy"),
+        });
+        let results = decode_batch(images, &[], &DecodeOptions::default(), 0);
+        assert_eq!(results.len(), 3);
+        assert!(results[..2].iter().all(Result::is_ok));
+        assert!(matches!(results[2], Err(Error::DecodeError(_))));
+
+        // asking for more workers than there are threads starts more
+        let threads = ThreadPool::shared().threads();
+        let images = (0..4).map(|_| render(ZxBarcodeFormat::QRCode, "more"));
+        let results = decode_batch(images, &[], &DecodeOptions::default(), threads + 2);
+        assert_eq!(results.len(), 4);
+        assert!(ThreadPool::shared().threads() >= threads + 2);
+    }
+
+    #[test]
+    fn test_spawn() {
+        let (sender, receiver) = mpsc::channel();
+        spawn(|| panic!("the pool outlives this"));
+        for i in 0..4 {
+            let sender = sender.clone();
+            spawn(move || sender.send(i).unwrap());
+        }
+        let mut received = receiver.iter().take(4).collect::<Vec<_>>();
+        received.sort();
+        assert_eq!(received, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_spawn_during_batch() {
+        struct Slow(Arc<AtomicUsize>);
+        impl IntoImage for Slow {
+            type Error = Error;
+
+            fn read_with<R: Send>(self, read: impl FnOnce(Image<'_>) -> R + Send) -> Result<R> {
+                thread::sleep(std::time::Duration::from_millis(20));
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(read(Image::new(
+                    vec![255; 64 * 64],
+                    64,
+                    64,
+                    ImageFormat::Lum,
+                )?))
+            }
+        }
+
+        let count = 25 * ThreadPool::shared().threads();
+        let done = Arc::new(AtomicUsize::new(0));
+        let images = (0..count).map({
+            let done = done.clone();
+            move |_| Slow(done.clone())
+        });
+        let batch = thread::spawn(move || decode_batch(images, &[], &DecodeOptions::default(), 0));
+        while done.load(Ordering::SeqCst) == 0 {
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        // a job queued meanwhile runs between the images of the batch
+        let (sender, receiver) = mpsc::channel();
+        spawn(move || sender.send(()).unwrap());
+        receiver.recv().unwrap();
+        assert!(done.load(Ordering::SeqCst) < count);
+        assert_eq!(batch.join().unwrap().len(), count);
+    }
+
+    #[test]
+    fn test_decode_options() {
+        let options = DecodeOptions::default();
+        assert_eq!(options.binarizer(), Binarizer::LocalAverage);
+        assert_eq!(options.text_mode(), TextMode::HRI);
+        assert_eq!(options.ean_add_on_symbol(), EanAddOnSymbol::Ignore);
+        assert!(!options.pure());
+        assert!(!options.return_errors());
+        assert!(options.try_harder());
+        assert!(options.try_rotate());
+        assert!(options.try_invert());
+        assert!(options.try_downscale());
+        assert_eq!(options.channel(), Channel::Rec601);
+
+        let options = options.with_try_rotate(false).with_try_invert(false);
+        assert!(options.try_harder());
+        assert!(!options.try_rotate());
+        assert!(!options.try_invert());
+        assert!(options.try_downscale());
+    }
+
+    fn render(format: ZxBarcodeFormat, content: &str) -> Image<'static> {
+        let image = zxingcpp::create(format)
+            .from_str(content)
+            .unwrap()
+            .to_image_with(&zxingcpp::write().scale(2))
+            .unwrap();
+        Image::new(
+            image.data(),
+            image.width() as u32,
+            image.height() as u32,
+            ImageFormat::Lum,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_decode_metadata() {
+        let options = DecodeOptions::default();
+
+        let image = render(ZxBarcodeFormat::QRCode, "hello");
+        let result = decode_single(image, &[], &options).unwrap().unwrap();
+        assert_eq!(result.ec_level(), Some("H"));
+        assert_eq!(result.version(), Some("1"));
+        assert!(!result.reader_init());
+
+        let image = render(ZxBarcodeFormat::DataMatrix, "hello");
+        let result = decode_single(image, &[], &options).unwrap().unwrap();
+        assert_eq!(result.ec_level(), None);
+        assert_eq!(result.version(), Some("12x12"));
+
+        let image = render(ZxBarcodeFormat::Code128, "hello");
+        let result = decode_single(image, &[], &options).unwrap().unwrap();
+        assert_eq!(result.ec_level(), None);
+        assert_eq!(result.version(), None);
+        assert!(!result.reader_init());
+    }
+
+    fn segment(index: usize, size: Option<usize>, text: &str) -> Segment {
+        Segment {
+            format: BarcodeFormat::QRCode,
+            id: "77".to_owned(),
+            index,
+            size,
+            text: text.to_owned(),
+            bytes: text.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_reassemble() {
+        let image = render(ZxBarcodeFormat::QRCode, "hello");
+        let result = decode_single(image, &[], &DecodeOptions::default())
+            .unwrap()
+            .unwrap();
+        assert!(!result.is_part_of_sequence());
+        assert_eq!(result.sequence_size(), None);
+        assert_eq!(result.sequence_index(), None);
+        assert_eq!(result.sequence_id(), None);
+        assert_eq!(result.segment(), None);
+
+        let messages = reassemble([segment(2, Some(3), "c"), segment(0, Some(3), "a")]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].missing(), vec![1]);
+        assert!(!messages[0].is_complete());
+        assert_eq!(messages[0].text(), "ac");
+
+        let messages = reassemble([
+            segment(2, Some(3), "c"),
+            segment(0, Some(3), "a"),
+            segment(1, Some(3), "b"),
+            segment(0, Some(3), "a"),
+        ]);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].is_complete());
+        assert_eq!(messages[0].text(), "abc");
+        assert_eq!(messages[0].bytes(), b"abc");
+
+        let messages = reassemble([segment(0, None, "a"), segment(2, None, "c")]);
+        assert_eq!(messages[0].size(), None);
+        assert_eq!(messages[0].missing(), vec![1]);
+        assert!(!messages[0].is_complete());
+    }
+
+    #[test]
+    fn test_decode_symbol() {
+        let options = DecodeOptions::default();
+
+        let image = render(ZxBarcodeFormat::QRCode, "hello");
+        let result = decode_single(image, &[], &options).unwrap().unwrap();
+        let symbol = result.symbol().unwrap();
+        assert_eq!((symbol.width(), symbol.height()), (21, 21));
+        assert_eq!(symbol.row_stride(), 3);
+        assert_eq!(symbol.as_bytes().len(), 3 * 21);
+        // top row of the top-left finder pattern followed by its separator
+        assert_eq!(
+            (0..8).map(|x| symbol.get(x, 0)).collect::<Vec<_>>(),
+            [true, true, true, true, true, true, true, false]
+        );
+        assert!(!symbol.get(1, 1));
+
+        let image = render(ZxBarcodeFormat::Code128, "hello");
+        let result = decode_single(image, &[], &options).unwrap().unwrap();
+        assert_eq!(result.symbol(), None);
+    }
+
+    #[test]
+    fn test_decode_points() {
+        let image = zxingcpp::create(ZxBarcodeFormat::QRCode)
+            .from_str("https://demo.net/demo/7809")
+            .unwrap()
+            .to_image_with(&zxingcpp::write().scale(4))
+            .unwrap();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let data = image.data();
+
+        // shear the symbol horizontally so that its outline is no longer an axis-aligned rectangle
+        let sheared_width = width + height / 2;
+        let mut sheared = vec![255; sheared_width * height];
+        for y in 0..height {
+            let offset = y / 2;
+            sheared[y * sheared_width + offset..y * sheared_width + offset + width]
+                .copy_from_slice(&data[y * width..(y + 1) * width]);
+        }
+        let image = Image::new(
+            sheared,
+            sheared_width as u32,
+            height as u32,
+            ImageFormat::Lum,
+        )
+        .unwrap();
+
+        let result = decode_single(image, &[], &DecodeOptions::default())
+            .unwrap()
+            .unwrap();
+        let [top_left, top_right, bottom_right, bottom_left] = result.points();
+        assert!(bottom_left.x() - top_left.x() > 20);
+        assert!(bottom_right.x() - top_right.x() > 20);
+        assert!((top_left.y() - top_right.y()).abs() <= 2);
+        assert!((bottom_left.y() - bottom_right.y()).abs() <= 2);
+    }
+
+    #[test]
+    fn test_decode_orientation_and_flags() {
+        let image = zxingcpp::create(ZxBarcodeFormat::QRCode)
+            .from_str("https://demo.net/demo/7809")
+            .unwrap()
+            .to_image_with(&zxingcpp::write().scale(4))
+            .unwrap();
+        let size = image.width() as usize;
+        let data = image.data();
+        let decode = |data: Vec<u8>| {
+            let image = Image::new(data, size as u32, size as u32, ImageFormat::Lum).unwrap();
+            decode_single(image, &[], &DecodeOptions::default())
+                .unwrap()
+                .unwrap()
+        };
+
+        let result = decode(data.clone());
+        assert_eq!(result.orientation(), 0);
+        assert!(!result.is_mirrored());
+        assert!(!result.is_inverted());
+
+        let rotated = (0..size * size)
+            .map(|i| data[(size - 1 - i % size) * size + i / size])
+            .collect::<Vec<_>>();
+        assert_eq!(decode(rotated).orientation().rem_euclid(360), 90);
+
+        let mirrored = (0..size * size)
+            .map(|i| data[i / size * size + size - 1 - i % size])
+            .collect::<Vec<_>>();
+        assert!(decode(mirrored).is_mirrored());
+
+        let inverted = data.iter().map(|v| 255 - v).collect::<Vec<_>>();
+        assert!(decode(inverted).is_inverted());
+    }
+
+    #[test]
+    fn test_decode_bytes() {
+        let payload = [0x00, 0x9f, 0xff, 0x80, 0x0a, 0xc3, 0x28];
+        let image = zxingcpp::create(ZxBarcodeFormat::QRCode)
+            .from_slice(payload)
+            .unwrap()
+            .to_image_with(&zxingcpp::write().scale(4))
+            .unwrap();
+        let image = Image::new(
+            image.data(),
+            image.width() as u32,
+            image.height() as u32,
+            ImageFormat::Lum,
+        )
+        .unwrap();
+
+        let result = decode_single(image, &[], &DecodeOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.bytes(), payload);
+        assert!(result.bytes_eci().starts_with(b"]Q"));
+        assert_eq!(result.content_type(), ContentType::Binary);
+    }
+
+    #[test]
+    fn test_decode_return_errors() {
+        let render_damaged = || {
+            let image = zxingcpp::create(ZxBarcodeFormat::QRCode)
+                .from_str("https://demo.net/demo/7809")
+                .unwrap()
+                .to_image_with(&zxingcpp::write().scale(4))
+                .unwrap();
+            let (width, height) = (image.width() as usize, image.height() as usize);
+            let mut data = image.data();
+            for y in height * 3 / 8..height * 5 / 8 {
+                data[y * width + width * 3 / 8..y * width + width * 5 / 8].fill(255);
+            }
+            Image::new(data, width as u32, height as u32, ImageFormat::Lum).unwrap()
+        };
+        let formats = [BarcodeFormat::QRCode];
+
+        let options = DecodeOptions::default();
+        assert!(
+            decode_single(render_damaged(), &formats, &options)
+                .unwrap()
+                .is_none()
+        );
+
+        let options = options.with_return_errors(true);
+        let result = decode_single(render_damaged(), &formats, &options)
+            .unwrap()
+            .unwrap();
+        assert!(!result.is_valid());
+        assert!(matches!(result.error(), Some(BarcodeError::Checksum(_))));
+        assert!(!result.error().unwrap().message().is_empty());
+
+        let image = render(ZxBarcodeFormat::QRCode, "https://demo.net/demo/7809");
+        let result = decode_single(image, &formats, &options).unwrap().unwrap();
+        assert!(result.is_valid());
+        assert_eq!(result.error(), None);
+    }
+
+    #[test]
+    fn test_decode_ean_add_on() {
+        let formats = [BarcodeFormat::EAN13];
+
+        let options = DecodeOptions::default();
+        let image = render(ZxBarcodeFormat::EAN13, "9780201379624+51299");
+        let result = decode_single(image, &formats, &options).unwrap().unwrap();
+        assert_eq!(result.text(), "9780201379624");
+        assert_eq!(result.main_text(), "9780201379624");
+        assert_eq!(result.add_on(), None);
+
+        let options = options.with_ean_add_on_symbol(EanAddOnSymbol::Require);
+        let image = render(ZxBarcodeFormat::EAN13, "9780201379624+51299");
+        let result = decode_single(image, &formats, &options).unwrap().unwrap();
+        assert_eq!(result.text(), "978020137962451299");
+        assert_eq!(result.main_text(), "9780201379624");
+        assert_eq!(result.add_on(), Some("51299"));
+
+        let image = render(ZxBarcodeFormat::EAN13, "9780201379624");
+        assert!(decode_single(image, &formats, &options).unwrap().is_none());
+
+        // the add-on is stripped in the rendering of the active text mode
+        for (text_mode, main_text) in [
+            (TextMode::Hex, "39 37 38 30 32 30 31 33 37 39 36 32 34"),
+            (TextMode::ECI, "]E3\\0000269780201379624"),
+            (TextMode::Escaped, "9780201379624"),
+        ] {
+            let options = options.with_text_mode(text_mode);
+            let image = render(ZxBarcodeFormat::EAN13, "9780201379624+51299");
+            let result = decode_single(image, &formats, &options).unwrap().unwrap();
+            assert_eq!(result.main_text(), main_text, "{}", text_mode);
+            assert_eq!(result.add_on(), Some("51299"));
+        }
     }
 }